@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER previews_fts_after_delete;
+DROP TRIGGER previews_fts_after_update;
+DROP TRIGGER previews_fts_after_insert;
+DROP TABLE previews_fts;
@@ -0,0 +1,29 @@
+-- Full-text index over previews. Rows are keyed by url rather than rowid, since
+-- the rowids of previews may change when the database is vacuumed. The
+-- content column holds extracted content, once it is stored.
+CREATE VIRTUAL TABLE previews_fts USING fts5(
+  url UNINDEXED,
+  title,
+  summary,
+  tags,
+  content,
+  tokenize = 'porter unicode61'
+);
+
+INSERT INTO previews_fts (url, title, summary, tags)
+SELECT url, title, summary, tags FROM previews;
+
+CREATE TRIGGER previews_fts_after_insert AFTER INSERT ON previews BEGIN
+  INSERT INTO previews_fts (url, title, summary, tags)
+  VALUES (new.url, new.title, new.summary, new.tags);
+END;
+
+CREATE TRIGGER previews_fts_after_update AFTER UPDATE ON previews BEGIN
+  UPDATE previews_fts
+  SET url = new.url, title = new.title, summary = new.summary, tags = new.tags
+  WHERE url = old.url;
+END;
+
+CREATE TRIGGER previews_fts_after_delete AFTER DELETE ON previews BEGIN
+  DELETE FROM previews_fts WHERE url = old.url;
+END;
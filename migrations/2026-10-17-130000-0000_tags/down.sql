@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE previews DROP COLUMN tags_provenance;
+DROP TABLE preview_tags;
+DROP TABLE tags;
@@ -0,0 +1,22 @@
+CREATE TABLE tags (
+  id INTEGER NOT NULL PRIMARY KEY,
+  name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE preview_tags (
+  preview_url TEXT NOT NULL REFERENCES previews (url) ON DELETE CASCADE ON UPDATE CASCADE,
+  tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+  -- where the tag came from: rss, arxiv, ai, manual or legacy
+  provenance TEXT NOT NULL,
+  PRIMARY KEY (preview_url, tag_id)
+);
+
+CREATE INDEX preview_tags_tag_id ON preview_tags (tag_id);
+
+-- where the tags in previews.tags came from, before they are normalized into
+-- preview_tags
+ALTER TABLE previews ADD COLUMN tags_provenance TEXT;
+
+-- the tags already in previews.tags are backfilled into preview_tags by
+-- `tags::backfill_legacy_tags` the next time linkstitcher starts, since they
+-- are normalized the same way as new tags
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER previews_fts_after_content_delete;
+DROP TRIGGER previews_fts_after_content_update;
+DROP TRIGGER previews_fts_after_content_insert;
+UPDATE previews_fts SET content = NULL;
+DROP TABLE preview_contents;
@@ -0,0 +1,22 @@
+CREATE TABLE preview_contents (
+  url TEXT NOT NULL PRIMARY KEY REFERENCES previews (url) ON DELETE CASCADE ON UPDATE CASCADE,
+  content TEXT NOT NULL,
+  content_type TEXT,
+  byte_length BIGINT NOT NULL,
+  -- how the content was extracted, e.g. readability or pdf
+  extraction_method TEXT NOT NULL,
+  fetched_at TIMESTAMP NOT NULL
+);
+
+-- keep previews_fts.content in sync with the stored content
+CREATE TRIGGER previews_fts_after_content_insert AFTER INSERT ON preview_contents BEGIN
+  UPDATE previews_fts SET content = new.content WHERE url = new.url;
+END;
+
+CREATE TRIGGER previews_fts_after_content_update AFTER UPDATE ON preview_contents BEGIN
+  UPDATE previews_fts SET content = new.content WHERE url = new.url;
+END;
+
+CREATE TRIGGER previews_fts_after_content_delete AFTER DELETE ON preview_contents BEGIN
+  UPDATE previews_fts SET content = NULL WHERE url = old.url;
+END;
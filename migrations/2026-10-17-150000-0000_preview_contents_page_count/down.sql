@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE preview_contents DROP COLUMN page_count;
@@ -0,0 +1,2 @@
+-- the number of pages of paged content, such as PDFs
+ALTER TABLE preview_contents ADD COLUMN page_count INTEGER;
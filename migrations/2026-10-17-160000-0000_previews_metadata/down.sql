@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE previews DROP COLUMN image_url;
+ALTER TABLE previews DROP COLUMN site_name;
+ALTER TABLE previews DROP COLUMN author;
@@ -0,0 +1,4 @@
+-- metadata that pages declare about themselves, e.g. with OpenGraph tags
+ALTER TABLE previews ADD COLUMN author TEXT;
+ALTER TABLE previews ADD COLUMN site_name TEXT;
+ALTER TABLE previews ADD COLUMN image_url TEXT;
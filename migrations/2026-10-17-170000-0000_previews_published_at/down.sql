@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX previews_published_at;
+ALTER TABLE previews DROP COLUMN published_at;
@@ -0,0 +1,9 @@
+-- the published date of a preview as a time in UTC, parsed from the free-form
+-- `published_date`
+ALTER TABLE previews ADD COLUMN published_at TIMESTAMP;
+
+-- the published dates that are already stored are parsed by
+-- `date::backfill_missing_published_at` the next time linkstitcher starts,
+-- since SQLite can only parse ISO 8601 dates
+
+CREATE INDEX previews_published_at ON previews (published_at);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE previews DROP COLUMN discussion_url;
+ALTER TABLE previews DROP COLUMN submitter;
+ALTER TABLE previews DROP COLUMN comment_count;
+ALTER TABLE previews DROP COLUMN score;
@@ -0,0 +1,5 @@
+-- the discussion of a preview on a site like Hacker News
+ALTER TABLE previews ADD COLUMN score INTEGER;
+ALTER TABLE previews ADD COLUMN comment_count INTEGER;
+ALTER TABLE previews ADD COLUMN submitter TEXT;
+ALTER TABLE previews ADD COLUMN discussion_url TEXT;
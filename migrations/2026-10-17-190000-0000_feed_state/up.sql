@@ -0,0 +1,7 @@
+-- how far each feed has read its source, so that the next run of the feed only
+-- fetches what's new
+CREATE TABLE feed_state (
+  feed_name TEXT NOT NULL PRIMARY KEY,
+  watermark TEXT NOT NULL,
+  updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,18 @@
+-- the metadata of arXiv papers, keyed by their ids without a version
+CREATE TABLE arxiv_papers (
+  arxiv_id TEXT NOT NULL PRIMARY KEY,
+  -- the latest version that has been seen
+  version INTEGER NOT NULL,
+  title TEXT NOT NULL,
+  -- a JSON array of the names of the authors, in order
+  authors TEXT NOT NULL,
+  summary TEXT NOT NULL,
+  published TEXT,
+  updated TEXT,
+  comment TEXT,
+  pdf_url TEXT,
+  primary_category TEXT,
+  -- comma-separated category codes, e.g. `cs.PL, cs.LO`
+  categories TEXT NOT NULL,
+  fetched_at TIMESTAMP NOT NULL
+);
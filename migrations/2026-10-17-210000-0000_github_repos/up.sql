@@ -0,0 +1,18 @@
+-- the metadata of GitHub repositories, keyed by `owner/name`
+CREATE TABLE github_repos (
+  full_name TEXT NOT NULL PRIMARY KEY,
+  owner TEXT NOT NULL,
+  name TEXT NOT NULL,
+  description TEXT,
+  stars INTEGER,
+  forks INTEGER,
+  -- the primary language, e.g. `Rust`
+  language TEXT,
+  -- comma-separated topics, e.g. `cli, rss`
+  topics TEXT NOT NULL,
+  -- the SPDX id of the license, e.g. `MIT`
+  license TEXT,
+  pushed_at TIMESTAMP,
+  archived BOOLEAN NOT NULL,
+  fetched_at TIMESTAMP NOT NULL
+);
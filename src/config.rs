@@ -9,12 +9,41 @@ macro_rules! load_env_var {
     };
 }
 
+macro_rules! load_optional_env_var {
+    ( $name: ident ) => {
+        lazy_static::lazy_static! {
+            pub static ref $name: Option<String> = std::env::var(stringify!($name)).ok();
+        }
+    };
+}
+
 load_env_var!(DATABASE_URL);
 load_env_var!(GITHUB_PERSONAL_ACCESS_TOKEN);
 load_env_var!(BOOKMARKED_URLS_FILEPATH);
 load_env_var!(SAVED_URLS_FILEPATH);
+load_optional_env_var!(LLM_BACKEND);
+load_optional_env_var!(LLM_BASE_URL);
+load_optional_env_var!(LLM_MODEL);
+load_optional_env_var!(LLM_API_KEY);
+load_optional_env_var!(LLM_MOCK_RESPONSE);
+load_optional_env_var!(HACKERNEWS_API_URL);
+load_optional_env_var!(ARXIV_API_URL);
+load_optional_env_var!(GITHUB_API_URL);
+load_optional_env_var!(X_OEMBED_URL);
+load_optional_env_var!(BLUESKY_API_URL);
 pub const REPOSITORY_URL: &str = "https://github.com/rybla/linkstitcher";
+/// Where the feeds directory is published.
+pub const SITE_URL: &str = "https://rybla.github.io/linkstitcher";
 pub const FEEDS_DIRPATH: &str = "site/";
+pub const FEEDS_CONFIG_FILEPATH: &str = "feeds.toml";
+pub const HTTP_CACHE_DIRPATH: &str = ".cache/http/";
 pub const RECENCY_CUTOFF: chrono::Days = chrono::Days::new(2);
 pub const MAX_RSS_FEED_ITEMS: usize = 5;
 pub const MAX_CHARS_SUMMARY: usize = 600;
+/// How many previews are embellished at once.
+pub const CONCURRENCY: usize = 8;
+/// How many previews from the same host are embellished at once.
+pub const PER_HOST_CONCURRENCY: usize = 2;
+/// How long to wait between requests for the pages of an arXiv query, as the
+/// arXiv API asks.
+pub const ARXIV_PAGE_DELAY: std::time::Duration = std::time::Duration::from_secs(3);
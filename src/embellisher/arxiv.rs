@@ -0,0 +1,57 @@
+use super::{Embellisher, ExtractedContent};
+use crate::{
+    Env,
+    models::{ArxivPaper, Preview, TagProvenance},
+    utility::{self, arxiv::Arxiv},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Embellishes arXiv abstract, PDF and HTML pages using the arXiv API.
+pub struct ArxivEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for ArxivEmbellisher {
+    fn name(&self) -> &str {
+        "arxiv"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        utility::arxiv::get_id_from_url(url).is_some()
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let mut content: Option<ExtractedContent> = None;
+
+        if let Some(arxiv_id) = utility::arxiv::get_id_from_url(&preview.url) {
+            if let Ok(article) = utility::arxiv::fetch_by_id(&env.fetcher, arxiv_id).await {
+                content = Some(apply_arxiv(preview, &article));
+            } else {
+                log::error!["failed to fetch ArXiv article: {}", preview.url];
+            }
+        }
+
+        Ok(content)
+    }
+}
+
+/// Fills in the fields of the preview from the arXiv paper, and returns its
+/// abstract as the extracted content.
+pub fn apply_arxiv(preview: &mut Preview, article: &Arxiv) -> ExtractedContent {
+    preview.title = Some(article.title.clone());
+    preview.set_published_date(Some(article.published.clone()));
+    if preview.source.is_none() {
+        preview.source = Some("ArXiv".to_owned())
+    }
+    preview.tags = Some(article.category_names.join(", "));
+    preview.tags_provenance = Some(TagProvenance::Arxiv.to_string());
+    preview.summary = Some(article.summary.clone());
+    ExtractedContent {
+        arxiv_paper: Some(ArxivPaper::from_arxiv(article)),
+        ..ExtractedContent::new(article.summary.clone(), "text/plain", "arxiv-abstract")
+    }
+}
@@ -0,0 +1,62 @@
+use super::{Embellisher, ExtractedContent, post_text, post_title};
+use crate::{
+    Env, config,
+    models::Preview,
+    utility::{self, bluesky::PostRef},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Embellishes Bluesky posts using the public API, with their text, author,
+/// date, media alt texts and links.
+pub struct BlueskyEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for BlueskyEmbellisher {
+    fn name(&self) -> &str {
+        "bluesky"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        PostRef::parse(url).is_some()
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let Some(post_ref) = PostRef::parse(&preview.url) else {
+            return Ok(None);
+        };
+        let post = match utility::bluesky::fetch_post(
+            &env.fetcher,
+            utility::bluesky::api_url(),
+            &post_ref,
+        )
+        .await
+        {
+            Ok(post) => post,
+            Err(e) => {
+                log::error!["failed to fetch Bluesky post {}: {e}", preview.url];
+                return Ok(None);
+            }
+        };
+
+        preview.title = Some(post_title(post.author_name(), "Bluesky", &post.record.text));
+        preview.author = Some(format!("@{}", post.author.handle));
+        if preview.site_name.is_none() {
+            preview.site_name = Some("Bluesky".to_owned());
+        }
+        if let Some(posted) = post.posted() {
+            preview.set_published_date(Some(posted.to_rfc3339()));
+        }
+        let text = post_text(&post.record.text, &post.alt_texts());
+        preview.summary = Some(text.chars().take(config::MAX_CHARS_SUMMARY).collect());
+
+        Ok(Some(ExtractedContent {
+            links: post.links(),
+            ..ExtractedContent::new(text, "text/plain", "bluesky-api")
+        }))
+    }
+}
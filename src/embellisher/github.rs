@@ -0,0 +1,160 @@
+use super::{Embellisher, ExtractedContent};
+use crate::{
+    Env, config,
+    models::{Preview, TagProvenance},
+    utility::{
+        self,
+        github::{GithubUrl, PageInfo, RepoInfo},
+    },
+};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+/// Embellishes GitHub repositories with their metadata and README, and other
+/// GitHub pages, like issues, releases, files and gists, with their content.
+pub struct GithubEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for GithubEmbellisher {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        GithubUrl::parse(url).is_some()
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let github_url = GithubUrl::parse(&preview.url)
+            .ok_or(anyhow!("not a GitHub repository or page: {}", preview.url))?;
+        if preview.source.is_none() {
+            preview.source = Some(github_url.source().to_owned());
+        }
+
+        let octocrab = match env.octocrab() {
+            Ok(octocrab) => octocrab,
+            Err(e) => {
+                log::error!["failed to fetch GitHub page {}: {e}", preview.url];
+                return Ok(None);
+            }
+        };
+
+        let mut content: Option<ExtractedContent> = None;
+        if let GithubUrl::Repo { owner, repo } = &github_url {
+            if let Ok(info) = utility::github::fetch_repo_info(octocrab, owner, repo).await {
+                content = Some(apply_repo_info(preview, &info));
+            } else {
+                log::error!["failed to fetch GitHub repo info: {}", preview.url];
+            }
+        } else {
+            match utility::github::fetch_page_info(octocrab, &github_url).await {
+                Ok(info) => content = apply_page_info(preview, info),
+                Err(e) => log::error!["failed to fetch GitHub page {}: {e}", preview.url],
+            }
+        }
+
+        Ok(content)
+    }
+}
+
+/// Fills in the fields of the preview from the repository, and returns its
+/// README, rendered as plain text, as the extracted content, or else its
+/// description. The repository's metadata comes with the content, which has no
+/// text if the repository has neither.
+pub fn apply_repo_info(preview: &mut Preview, info: &RepoInfo) -> ExtractedContent {
+    let readme = info
+        .readme
+        .as_deref()
+        .map(utility::github::markdown_to_text);
+
+    preview.title = Some(info.title());
+    if preview.site_name.is_none() {
+        preview.site_name = Some("GitHub".to_owned());
+    }
+    preview.author = Some(info.owner.clone());
+    if !info.topics.is_empty() {
+        preview.tags = Some(info.topics.join(", "));
+        preview.tags_provenance = Some(TagProvenance::Github.to_string());
+    }
+    preview.summary = readme
+        .as_deref()
+        .or(info.description.as_deref())
+        .map(|s| s.chars().take(config::MAX_CHARS_SUMMARY).collect());
+
+    let (text, extraction_method) = match (readme, &info.description) {
+        (Some(readme), _) => (readme, "github-readme"),
+        (None, Some(description)) => (description.trim().to_owned(), "github-description"),
+        (None, None) => (String::new(), "github-description"),
+    };
+    ExtractedContent {
+        github_repo: Some(info.to_github_repo()),
+        ..ExtractedContent::new(text, "text/plain", extraction_method)
+    }
+}
+
+/// Fills in the fields of the preview from a GitHub page other than a
+/// repository, and returns its text as the extracted content.
+pub fn apply_page_info(preview: &mut Preview, info: PageInfo) -> Option<ExtractedContent> {
+    preview.title = Some(info.title);
+    if preview.site_name.is_none() {
+        preview.site_name = Some("GitHub".to_owned());
+    }
+    if info.author.is_some() {
+        preview.author = info.author;
+    }
+    if let Some(published_at) = info.published_at {
+        preview.set_published_date(Some(published_at.to_rfc3339()));
+    }
+    if !info.tags.is_empty() {
+        preview.tags = Some(info.tags.join(", "));
+        preview.tags_provenance = Some(TagProvenance::Github.to_string());
+    }
+    if let Some(comment_count) = info.comment_count {
+        preview.comment_count = Some(comment_count as i32);
+    }
+    preview.summary = info
+        .text
+        .as_ref()
+        .map(|s| s.chars().take(config::MAX_CHARS_SUMMARY).collect());
+
+    info.text
+        .map(|text| ExtractedContent::new(text, "text/plain", "github"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::github::test_repo_info;
+
+    #[test]
+    fn extracts_the_readme_as_plain_text() {
+        let mut preview = Preview::from_url("https://github.com/rust-lang/rust".to_owned());
+        let content = apply_repo_info(
+            &mut preview,
+            &test_repo_info(Some("A language"), Some("# Rust\n\n**Fast**.")),
+        );
+        assert_eq!(preview.title.as_deref(), Some("rust-lang/rust: A language"));
+        assert_eq!(preview.summary.as_deref(), Some("Rust\nFast."));
+        assert_eq!(preview.tags.as_deref(), Some("compiler"));
+        assert_eq!(content.text, "Rust\nFast.");
+        assert_eq!(content.content_type.as_deref(), Some("text/plain"));
+        assert_eq!(content.extraction_method, "github-readme");
+        assert_eq!(content.github_repo.unwrap().stars, Some(100));
+    }
+
+    #[test]
+    fn extracts_the_description_without_a_readme() {
+        let mut preview = Preview::from_url("https://github.com/rust-lang/rust".to_owned());
+        let content = apply_repo_info(&mut preview, &test_repo_info(Some("A language"), None));
+        assert_eq!(content.text, "A language");
+        assert_eq!(content.extraction_method, "github-description");
+
+        let content = apply_repo_info(&mut preview, &test_repo_info(None, None));
+        assert!(content.text.is_empty());
+        assert!(content.github_repo.is_some());
+    }
+}
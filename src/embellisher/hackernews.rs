@@ -0,0 +1,54 @@
+use super::{Embellisher, ExtractedContent};
+use crate::{Env, models::Preview, utility, utility::hackernews};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+
+/// Embellishes the discussion of a Hacker News story. A story that links
+/// somewhere becomes a preview of the page it links to, which is embellished
+/// by whichever embellisher matches that page.
+pub struct HackerNewsEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for HackerNewsEmbellisher {
+    fn name(&self) -> &str {
+        "hackernews"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        hackernews::get_item_id_from_url(url).is_some()
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let id = hackernews::get_item_id_from_url(&preview.url)
+            .ok_or(anyhow!("not a Hacker News item: {}", preview.url))?;
+        let item = hackernews::fetch_item(&env.fetcher, hackernews::api_url(), id)
+            .await?
+            .ok_or(anyhow!("no such Hacker News item: {id}"))?;
+        item.apply_to(preview);
+        if preview.source.is_none() {
+            preview.source = Some("Hacker News".to_owned());
+        }
+
+        let Some(url) = &item.url else {
+            // a text post, like Ask HN
+            return Ok(item
+                .plain_text()
+                .map(|text| ExtractedContent::new(text, "text/plain", "hackernews")));
+        };
+
+        // whoever stores the preview reconciles it with any preview already
+        // stored at the new URL, see `canonical::reconcile_rewritten_url`
+        preview.url = utility::canonical::canonicalize_url(url);
+        log::info!["hackernews: item {id} links to {}", preview.url];
+        match env.embellishers.find(&preview.url) {
+            Some(embellisher) if embellisher.name() != self.name() => {
+                embellisher.embellish(env, preview).await
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use super::{Embellisher, ExtractedContent, post_text, post_title, web};
+use crate::{
+    Env, config,
+    models::Preview,
+    utility::{self, mastodon::StatusRef},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Embellishes statuses on Mastodon instances, and other fediverse servers
+/// with its API, with their text, author, date, media alt texts and links.
+/// Pages that only look like statuses are embellished as any other web page.
+pub struct MastodonEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for MastodonEmbellisher {
+    fn name(&self) -> &str {
+        "mastodon"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        StatusRef::parse(url).is_some()
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let Some(status_ref) = StatusRef::parse(&preview.url) else {
+            return web::WebEmbellisher.embellish(env, preview).await;
+        };
+        let status = match utility::mastodon::fetch_status(&env.fetcher, &status_ref).await {
+            Ok(status) => status,
+            Err(e) => {
+                log::warn!["failed to fetch Mastodon status {}: {e}", preview.url];
+                return web::WebEmbellisher.embellish(env, preview).await;
+            }
+        };
+
+        let text = status.text();
+        let handle = status.author_handle();
+        preview.title = Some(post_title(status.author_name(), "Mastodon", &text));
+        preview.author = Some(handle.clone());
+        if preview.site_name.is_none() {
+            preview.site_name = handle.rsplit_once('@').map(|(_, domain)| domain.to_owned());
+        }
+        if let Some(posted) = status.posted() {
+            preview.set_published_date(Some(posted.to_rfc3339()));
+        }
+        let text = post_text(&text, &status.alt_texts());
+        preview.summary = Some(text.chars().take(config::MAX_CHARS_SUMMARY).collect());
+
+        Ok(Some(ExtractedContent {
+            links: status.links(),
+            ..ExtractedContent::new(text, "text/plain", "mastodon-api")
+        }))
+    }
+}
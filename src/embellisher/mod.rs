@@ -0,0 +1,205 @@
+//! Site-specific strategies for embellishing previews.
+//!
+//! Each [`Embellisher`] claims the URLs it knows how to handle and fills in
+//! whatever it can about a preview. [`Embellishers`] is the registry that
+//! [`crate::embellish_preview`] consults, so crates depending on linkstitcher
+//! can register their own embellishers on [`crate::Env`] without touching the
+//! built-in ones.
+use crate::{
+    Env,
+    models::{ArxivPaper, GithubRepo, Preview},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub mod arxiv;
+pub mod bluesky;
+pub mod github;
+pub mod hackernews;
+pub mod mastodon;
+pub mod web;
+pub mod x;
+
+#[async_trait(?Send)]
+pub trait Embellisher {
+    /// A short, human-readable name, used for logging.
+    fn name(&self) -> &str;
+
+    /// Whether this embellisher knows how to handle `url`.
+    fn matches(&self, url: &str) -> bool;
+
+    /// Fills in the fields of `preview` and returns the extracted content, if
+    /// any.
+    async fn embellish(&self, env: &Env, preview: &mut Preview)
+    -> Result<Option<ExtractedContent>>;
+}
+
+/// The full content extracted from a preview's URL while embellishing it.
+#[derive(Debug, Clone)]
+pub struct ExtractedContent {
+    pub text: String,
+    /// The MIME type of the content that the text was extracted from.
+    pub content_type: Option<String>,
+    /// How the text was extracted, e.g. `readability` or `pdf`.
+    pub extraction_method: String,
+    /// The number of pages of paged content, such as PDFs.
+    pub page_count: Option<i32>,
+    /// The metadata of the arXiv paper that the content is the abstract of,
+    /// which is stored on the side.
+    pub arxiv_paper: Option<ArxivPaper>,
+    /// The metadata of the GitHub repository that the content is the README
+    /// of, which is stored on the side, even if the content has no text.
+    pub github_repo: Option<GithubRepo>,
+    /// The links that the content leads to, which may be added as previews of
+    /// their own.
+    pub links: Vec<String>,
+}
+
+impl ExtractedContent {
+    pub fn new(text: String, content_type: &str, extraction_method: &str) -> Self {
+        ExtractedContent {
+            text,
+            content_type: Some(content_type.to_owned()),
+            extraction_method: extraction_method.to_owned(),
+            page_count: None,
+            arxiv_paper: None,
+            github_repo: None,
+            links: vec![],
+        }
+    }
+}
+
+/// How many characters of the text of a social media post are quoted in its
+/// title.
+const MAX_CHARS_TITLE_QUOTE: usize = 100;
+
+/// A title for a post on a social media site that quotes the start of its
+/// text, e.g. `jack on X: "just setting up my twttr"`, or just the author and
+/// site for a post without text.
+fn post_title(author_name: &str, site_name: &str, text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return format!("{author_name} on {site_name}");
+    }
+    let mut quote: String = text.chars().take(MAX_CHARS_TITLE_QUOTE).collect();
+    if quote.len() < text.len() {
+        quote.push('…');
+    }
+    format!("{author_name} on {site_name}: \"{quote}\"")
+}
+
+/// The text of a social media post followed by the alt texts of its media,
+/// which are often all there is to say what an image post is about.
+fn post_text(text: &str, alt_texts: &[String]) -> String {
+    let mut text = text.trim().to_owned();
+    for alt_text in alt_texts {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(&format!("[{alt_text}]"));
+    }
+    text
+}
+
+/// An ordered registry of embellishers. Embellishers registered later take
+/// precedence over those registered earlier.
+pub struct Embellishers {
+    embellishers: Vec<Box<dyn Embellisher>>,
+}
+
+impl Embellishers {
+    /// A registry without any embellishers.
+    pub fn empty() -> Self {
+        Embellishers {
+            embellishers: vec![],
+        }
+    }
+
+    pub fn register(&mut self, embellisher: impl Embellisher + 'static) {
+        self.embellishers.push(Box::new(embellisher));
+    }
+
+    /// Finds the most recently registered embellisher that matches `url`.
+    pub fn find(&self, url: &str) -> Option<&dyn Embellisher> {
+        self.embellishers
+            .iter()
+            .rev()
+            .find(|embellisher| embellisher.matches(url))
+            .map(|embellisher| embellisher.as_ref())
+    }
+}
+
+impl Default for Embellishers {
+    /// The built-in embellishers, with [`web::WebEmbellisher`] as the fallback
+    /// for any URL that isn't handled by a site-specific one.
+    fn default() -> Self {
+        let mut embellishers = Embellishers::empty();
+        embellishers.register(web::WebEmbellisher);
+        embellishers.register(github::GithubEmbellisher);
+        embellishers.register(x::XEmbellisher);
+        embellishers.register(mastodon::MastodonEmbellisher);
+        embellishers.register(bluesky::BlueskyEmbellisher);
+        embellishers.register(arxiv::ArxivEmbellisher);
+        embellishers.register(hackernews::HackerNewsEmbellisher);
+        embellishers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Everything;
+
+    #[async_trait(?Send)]
+    impl Embellisher for Everything {
+        fn name(&self) -> &str {
+            "everything"
+        }
+
+        fn matches(&self, _url: &str) -> bool {
+            true
+        }
+
+        async fn embellish(
+            &self,
+            _env: &Env,
+            _preview: &mut Preview,
+        ) -> Result<Option<ExtractedContent>> {
+            Ok(None)
+        }
+    }
+
+    fn found(embellishers: &Embellishers, url: &str) -> Option<String> {
+        embellishers
+            .find(url)
+            .map(|embellisher| embellisher.name().to_owned())
+    }
+
+    #[test]
+    fn falls_back_to_the_web_embellisher() {
+        let embellishers = Embellishers::default();
+        assert_eq!(
+            found(&embellishers, "https://example.com/post").as_deref(),
+            Some("web")
+        );
+        assert_eq!(
+            found(&embellishers, "https://arxiv.org/abs/2410.12345").as_deref(),
+            Some("arxiv")
+        );
+        assert_eq!(found(&Embellishers::empty(), "https://example.com/"), None);
+    }
+
+    #[test]
+    fn prefers_later_registrations() {
+        let mut embellishers = Embellishers::default();
+        embellishers.register(Everything);
+        for url in [
+            "https://example.com/post",
+            "https://arxiv.org/abs/2410.12345",
+            "https://github.com/rust-lang/rust",
+        ] {
+            assert_eq!(found(&embellishers, url).as_deref(), Some("everything"));
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use super::{Embellisher, ExtractedContent};
+use crate::{Env, models::Preview, utility, utility::mime::ContentKind};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// The longest title that is taken from the first line of some text.
+const MAX_CHARS_TITLE: usize = 200;
+
+/// Embellishes any web page by fetching it and extracting its content
+/// according to its content type (see [`utility::mime::detect`]).
+pub struct WebEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for WebEmbellisher {
+    fn name(&self) -> &str {
+        "web"
+    }
+
+    fn matches(&self, _url: &str) -> bool {
+        true
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let mut content: Option<ExtractedContent> = None;
+
+        // fetch content at URL
+        let response = env.fetcher.get(&preview.url).await?;
+        let content_type = utility::mime::detect(
+            response.content_type().as_deref(),
+            &response.url,
+            &response.body,
+        );
+        let essence = content_type.essence_str();
+
+        // extract content
+        match ContentKind::of(&content_type) {
+            ContentKind::Html => {
+                let html = response.text();
+                if let Some(url) = utility::canonical::canonical_link(&html, &preview.url)
+                    && url != preview.url
+                {
+                    log::info!["canonical url of {}: {url}", preview.url];
+                    preview.url = url;
+                }
+
+                // the metadata that the page declares takes precedence over
+                // what Readability makes out of it
+                let metadata = utility::metadata::extract(&html, &preview.url);
+                if preview.summary.is_none() {
+                    preview.summary = metadata.description;
+                }
+                preview.author = metadata.author.or(preview.author.take());
+                preview.site_name = metadata.site_name.or(preview.site_name.take());
+                preview.image_url = metadata.image_url.or(preview.image_url.take());
+                if metadata.published_time.is_some() {
+                    preview.set_published_date(metadata.published_time);
+                }
+
+                match utility::readability::parse(html.clone(), preview.url.clone()).await {
+                    Err(e) => {
+                        log::warn!["failed to use Readability to parse with url: {e}"];
+                        preview.title = Some(
+                            metadata
+                                .title
+                                .or_else(|| first_html_heading(&html))
+                                .unwrap_or(preview.url.clone()),
+                        );
+                    }
+                    Ok(article) => {
+                        preview.title = Some(metadata.title.unwrap_or(article.title.clone()));
+                        if preview.published_date().is_none() {
+                            preview.set_published_date(article.published_time);
+                        }
+
+                        content = Some(ExtractedContent::new(
+                            article.text_content.clone(),
+                            essence,
+                            "readability",
+                        ));
+                    }
+                }
+            }
+            ContentKind::Pdf => {
+                // extracting the text is CPU-bound, like Readability
+                let body = response.body.clone();
+                let pdf =
+                    tokio::task::spawn_blocking(move || utility::pdf::extract(&body)).await??;
+                preview.title = pdf
+                    .title
+                    .or_else(|| first_line(&pdf.text))
+                    .or(preview.title.take());
+                content = Some(ExtractedContent {
+                    page_count: Some(pdf.page_count as i32),
+                    ..ExtractedContent::new(pdf.text, essence, "pdf")
+                });
+            }
+            ContentKind::Markdown => {
+                let text = response.text();
+                preview.title = first_markdown_heading(&text)
+                    .or_else(|| first_line(&text))
+                    .or(preview.title.take());
+                content = Some(ExtractedContent::new(text, essence, "markdown"));
+            }
+            ContentKind::Text => {
+                let text = response.text();
+                preview.title = first_line(&text).or(preview.title.take());
+                content = Some(ExtractedContent::new(text, essence, "text"));
+            }
+            ContentKind::Image | ContentKind::Binary => {
+                log::info!["no text to extract from {essence}: {}", preview.url];
+                if preview.title.is_none() {
+                    preview.title = file_name(&response.url);
+                }
+            }
+        }
+
+        Ok(content)
+    }
+}
+
+/// The first non-empty line of the text, as a title.
+fn first_line(text: &str) -> Option<String> {
+    let line = text
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())?;
+    Some(line.chars().take(MAX_CHARS_TITLE).collect())
+}
+
+/// The first ATX heading of the Markdown, e.g. `Title` for `# Title`.
+fn first_markdown_heading(markdown: &str) -> Option<String> {
+    markdown.lines().find_map(|line| {
+        let heading = line.trim_start().strip_prefix("#")?.trim_start_matches("#");
+        if !heading.starts_with(" ") {
+            return None;
+        }
+        let heading = heading.trim().trim_end_matches("#").trim();
+        (!heading.is_empty()).then(|| heading.to_owned())
+    })
+}
+
+/// The text of the `<title>`, or else the first `<h1>`, of the HTML.
+fn first_html_heading(html: &str) -> Option<String> {
+    let document = scraper::Html::parse_document(html);
+    ["title", "h1"].iter().find_map(|selector| {
+        let selector = scraper::Selector::parse(selector).ok()?;
+        let text = document
+            .select(&selector)
+            .next()?
+            .text()
+            .collect::<String>();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    })
+}
+
+/// The name of the file at the URL, e.g. `paper.pdf`.
+fn file_name(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let file_name = url.path_segments()?.next_back()?;
+    let file_name = urlencoding::decode(file_name).ok()?;
+    (!file_name.is_empty()).then(|| file_name.into_owned())
+}
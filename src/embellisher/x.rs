@@ -0,0 +1,62 @@
+use super::{Embellisher, ExtractedContent, post_title};
+use crate::{
+    Env, config,
+    models::Preview,
+    utility::{self, x::PostRef},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Embellishes X posts using the oEmbed API, with their text, author, date and
+/// outbound links.
+pub struct XEmbellisher;
+
+#[async_trait(?Send)]
+impl Embellisher for XEmbellisher {
+    fn name(&self) -> &str {
+        "x"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        PostRef::parse(url).is_some()
+    }
+
+    async fn embellish(
+        &self,
+        env: &Env,
+        preview: &mut Preview,
+    ) -> Result<Option<ExtractedContent>> {
+        let mut content: Option<ExtractedContent> = None;
+
+        if let Ok(post) =
+            utility::x::fetch_post(&env.fetcher, utility::x::oembed_url(), &preview.url).await
+        {
+            let parsed = utility::x::parse_post_html(&post.html);
+            let handle = parsed.handle.clone().or_else(|| post.author_handle());
+
+            preview.title = Some(post_title(&post.author_name, "X", &parsed.text));
+            preview.author = handle.map(|handle| format!("@{handle}"));
+            if preview.site_name.is_none() {
+                preview.site_name = Some("X".to_owned());
+            }
+            if let Some(posted) = parsed.posted {
+                preview.set_published_date(Some(posted.to_rfc3339()));
+            }
+            preview.summary = Some(
+                parsed
+                    .text
+                    .chars()
+                    .take(config::MAX_CHARS_SUMMARY)
+                    .collect(),
+            );
+            content = Some(ExtractedContent {
+                links: parsed.links,
+                ..ExtractedContent::new(parsed.text, "text/plain", "x-oembed")
+            });
+        } else {
+            log::error!["failed to fetch X post: {}", preview.url];
+        }
+
+        Ok(content)
+    }
+}
@@ -0,0 +1,455 @@
+//! Declarative feed definitions.
+//!
+//! Each feed in the feeds file (see [`config::FEEDS_CONFIG_FILEPATH`]) names an
+//! upstream source of previews, the [`SmartFilter`] to pass them through, and
+//! the feed to publish them to. [`run_feed`] is the one generic runner
+//! that processes any of them.
+use crate::{
+    Env, config,
+    embellisher::{self, ExtractedContent},
+    get_recent_saved_previews,
+    models::Preview,
+    pipeline,
+    rss_channel::{self, SmartFilter},
+    utility::{
+        self,
+        arxiv::ArxivQueryBuilder,
+        db::DbWriter,
+        feed::{FeedFormat, FeedMeta},
+    },
+};
+use anyhow::{Result, anyhow};
+use chrono::Days;
+use diesel::prelude::*;
+use futures::{StreamExt, stream};
+use serde::Deserialize;
+use std::fs;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedsConfig {
+    #[serde(default)]
+    pub feeds: Vec<FeedConfig>,
+}
+
+impl FeedsConfig {
+    pub fn load(file_path: &str) -> Result<Self> {
+        let content = fs::read_to_string(file_path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FeedConfig> {
+        self.feeds.iter().find(|feed| feed.name == name)
+    }
+
+    /// Selects the feeds with the given names, or all feeds if no names are
+    /// given.
+    pub fn select(&self, names: &[String]) -> Result<Vec<FeedConfig>> {
+        if names.is_empty() {
+            return Ok(self.feeds.clone());
+        }
+        names
+            .iter()
+            .map(|name| {
+                self.get(name)
+                    .cloned()
+                    .ok_or(anyhow!("unknown feed: {name}"))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedConfig {
+    /// A unique name for the feed, used to select it when running feeds.
+    pub name: String,
+    pub source: FeedSource,
+    #[serde(default)]
+    pub filter: SmartFilter,
+    /// The file name of the published RSS channel, relative to the feeds
+    /// directory. The other formats are written next to it.
+    pub filename: String,
+    /// The formats the feed is published in. Defaults to all of them.
+    #[serde(default = "default_formats")]
+    pub formats: Vec<FeedFormat>,
+    pub title: String,
+    pub description: String,
+    /// Only previews added within this many days are published.
+    pub recency_cutoff_days: Option<u64>,
+}
+
+impl FeedConfig {
+    pub fn recency_cutoff(&self) -> Days {
+        self.recency_cutoff_days
+            .map(Days::new)
+            .unwrap_or(config::RECENCY_CUTOFF)
+    }
+
+    pub fn meta(&self) -> FeedMeta {
+        let stem = self
+            .filename
+            .strip_suffix(".feed.xml")
+            .or(self.filename.strip_suffix(".xml"))
+            .unwrap_or(&self.filename);
+        FeedMeta {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            stem: stem.to_owned(),
+        }
+    }
+}
+
+fn default_formats() -> Vec<FeedFormat> {
+    FeedFormat::ALL.to_vec()
+}
+
+fn default_hacker_news_limit() -> usize {
+    30
+}
+
+fn default_arxiv_page_size() -> usize {
+    100
+}
+
+fn default_arxiv_max_results() -> usize {
+    500
+}
+
+fn default_github_releases_per_repo() -> usize {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedSource {
+    /// An upstream RSS channel. Its items are labeled with `source`, which is
+    /// also used to select the previews that are published.
+    Rss { url: String, source: String },
+    /// The best stories on Hacker News, from its API (see
+    /// [`utility::hackernews`]), with their discussions. Like the items of an
+    /// RSS channel, they are labeled with `source`.
+    HackerNews {
+        source: String,
+        /// How many of the best stories are fetched.
+        #[serde(default = "default_hacker_news_limit")]
+        limit: usize,
+    },
+    /// The papers that match an arXiv search query, e.g.
+    /// `cat:cs.PL AND abs:effects`, newest first. Each run pages through the
+    /// papers submitted since a few days before the newest one that the last
+    /// run saw (see [`utility::arxiv::fetch_new_articles`]). Like the items of
+    /// an RSS channel, they are labeled with `source`.
+    Arxiv {
+        query: String,
+        source: String,
+        /// How many papers are requested per page.
+        #[serde(default = "default_arxiv_page_size")]
+        page_size: usize,
+        /// The most papers that are fetched in one run.
+        #[serde(default = "default_arxiv_max_results")]
+        max_results: usize,
+    },
+    /// The new releases of GitHub repositories: those listed in `repos`, as
+    /// `owner/name`, and, with `starred`, those that the authenticated user has
+    /// starred. Each run fetches what was published since the newest release
+    /// of each repository that the last run saw, and the tags that it didn't
+    /// see; the first run of a repository only notes where it is (see
+    /// [`utility::github_releases::fetch_new_releases`]). Like the items of an
+    /// RSS channel, they are labeled with `source`.
+    GithubReleases {
+        #[serde(default)]
+        repos: Vec<String>,
+        #[serde(default)]
+        starred: bool,
+        source: String,
+        /// Whether tags that aren't releases are included too.
+        #[serde(default)]
+        tags: bool,
+        /// The most releases, and tags, that are fetched per repository in one
+        /// run.
+        #[serde(default = "default_github_releases_per_repo")]
+        per_repo: usize,
+    },
+    /// The URLs listed in [`struct@config::SAVED_URLS_FILEPATH`], which is
+    /// cleared after they are processed.
+    Saved,
+}
+
+/// Runs each of the feeds in order. A feed that fails is logged and doesn't
+/// prevent the rest from running.
+pub async fn run_feeds(env: &mut Env, feeds: &[FeedConfig]) -> Result<()> {
+    for feed in feeds {
+        if let Err(e) = run_feed(env, feed).await {
+            log::error!["Error during run_feed for {}: {e}", feed.name];
+        }
+    }
+    Ok(())
+}
+
+/// Fetches new previews from the feed's source, embellishes and filters them
+/// concurrently (see [`pipeline`]), stores them, and then writes the feed.
+pub async fn run_feed(env: &mut Env, feed: &FeedConfig) -> Result<()> {
+    log::info!["run_feed: {}", feed.name];
+
+    // fetch previews
+    let (previews, watermark) = fetch_new_previews(env, feed).await?;
+
+    // embellish, filter and store previews
+    if env.dry_run {
+        for preview in pipeline::process_previews(env, &feed.filter, previews, None).await {
+            log::info!["dry run: would insert {}", preview.url];
+        }
+        return Ok(());
+    }
+    let writer = DbWriter::spawn(&env.database_url);
+    let previews = pipeline::process_previews(env, &feed.filter, previews, Some(&writer)).await;
+    let failures = writer.finish()?;
+    log::info![
+        "run_feed: stored {} new previews for {}, of which {failures} writes failed",
+        previews.len(),
+        feed.name
+    ];
+    if let Some(watermark) = watermark {
+        utility::db::set_feed_watermark(&mut env.db_conn, &feed.name, &watermark)?;
+    }
+
+    render_feed(env, feed)?;
+
+    // clear urls
+    if let FeedSource::Saved = feed.source {
+        fs::write(config::SAVED_URLS_FILEPATH.as_str(), String::new())?;
+    }
+
+    Ok(())
+}
+
+/// Writes the feed in each of its formats from the previews already stored,
+/// without fetching anything.
+pub fn render_feed(env: &mut Env, feed: &FeedConfig) -> Result<()> {
+    log::info!["render_feed: {}", feed.name];
+
+    if env.dry_run {
+        log::info![
+            "dry run: would write {} into {}",
+            feed.name,
+            env.feeds_dirpath
+        ];
+        return Ok(());
+    }
+
+    let previews = get_feed_previews(env, feed)?;
+    let urls: Vec<String> = previews.iter().map(|preview| preview.url.clone()).collect();
+    let contents = utility::db::get_preview_contents(&mut env.db_conn, &urls)?;
+    utility::feed::write_feed(
+        &env.feeds_dirpath,
+        &feed.meta(),
+        &previews,
+        &contents,
+        &feed.formats,
+    )?;
+
+    Ok(())
+}
+
+/// Fetches the previews from the feed's source that aren't already known, once
+/// each, with any content that the source already extracted. Also returns how
+/// far the source has now been read, for a source that keeps track (see
+/// [`utility::db::get_feed_watermark`]).
+async fn fetch_new_previews(
+    env: &mut Env,
+    feed: &FeedConfig,
+) -> Result<(Vec<(Preview, Option<ExtractedContent>)>, Option<String>)> {
+    let mut previews = vec![];
+    let mut watermark = None;
+
+    match &feed.source {
+        FeedSource::Rss { url, source } => {
+            let Some(channel) = utility::rss::fetch_rss_channel(&env.fetcher, url).await? else {
+                return Ok((vec![], None));
+            };
+            for mut preview in rss_channel::into_previews(channel)? {
+                if utility::db::is_url_known(&mut env.db_conn, &preview.url)? {
+                    continue;
+                }
+                preview.source = Some(source.to_owned());
+                previews.push((preview, None));
+            }
+        }
+        FeedSource::HackerNews { source, limit } => {
+            let api_url = utility::hackernews::api_url();
+            let ids = utility::hackernews::fetch_best_stories(&env.fetcher, api_url).await?;
+            let items: Vec<_> = stream::iter(ids.into_iter().take(*limit))
+                .map(|id| utility::hackernews::fetch_item(&env.fetcher, api_url, id))
+                .buffered(env.concurrency)
+                .collect()
+                .await;
+            for item in items {
+                let item = match item {
+                    Ok(Some(item)) if !(item.deleted || item.dead) => item,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        log::warn!["failed to fetch Hacker News item: {e}"];
+                        continue;
+                    }
+                };
+                let mut preview =
+                    Preview::from_url(item.url.clone().unwrap_or(item.discussion_url()));
+                if utility::db::is_url_known(&mut env.db_conn, &preview.url)? {
+                    continue;
+                }
+                item.apply_to(&mut preview);
+                preview.source = Some(source.to_owned());
+                previews.push((preview, None));
+            }
+        }
+        FeedSource::Arxiv {
+            query,
+            source,
+            page_size,
+            max_results,
+        } => {
+            let last_published = utility::db::get_feed_watermark(&mut env.db_conn, &feed.name)?
+                .as_deref()
+                .and_then(utility::date::parse_date);
+            let new_articles = utility::arxiv::fetch_new_articles(
+                &env.fetcher,
+                &ArxivQueryBuilder::new().search_query(&urlencoding::encode(query)),
+                *page_size,
+                *max_results,
+                last_published,
+                config::ARXIV_PAGE_DELAY,
+            )
+            .await?;
+
+            for article in new_articles.articles {
+                let mut preview = Preview::from_url(article.id.clone());
+                // the runs overlap, so some papers were seen before
+                if utility::db::is_url_known(&mut env.db_conn, &preview.url)? {
+                    continue;
+                }
+                preview.source = Some(source.to_owned());
+                // the query already responded with everything that
+                // embellishing would fetch
+                let content = embellisher::arxiv::apply_arxiv(&mut preview, &article);
+                preview.embellished = true;
+                previews.push((preview, Some(content)));
+            }
+
+            if new_articles.last_published != last_published {
+                watermark = new_articles.last_published.map(|time| time.to_rfc3339());
+            }
+        }
+        FeedSource::GithubReleases {
+            repos,
+            starred,
+            source,
+            tags,
+            per_repo,
+        } => {
+            let octocrab = &env.octocrab()?.clone();
+            let mut full_names = repos.clone();
+            if *starred {
+                for full_name in utility::github_releases::fetch_starred_repos(octocrab).await? {
+                    if !full_names.contains(&full_name) {
+                        full_names.push(full_name);
+                    }
+                }
+            }
+
+            let last_watermark = utility::db::get_feed_watermark(&mut env.db_conn, &feed.name)?;
+            let mut watermarks =
+                utility::github_releases::parse_watermarks(last_watermark.as_deref());
+            let results: Vec<_> = stream::iter(&full_names)
+                .map(|full_name| {
+                    let repo_watermark = watermarks.get(full_name).cloned();
+                    async move {
+                        utility::github_releases::fetch_new_releases(
+                            octocrab,
+                            full_name,
+                            repo_watermark.as_ref(),
+                            *tags,
+                            *per_repo,
+                        )
+                        .await
+                    }
+                })
+                .buffered(env.concurrency)
+                .collect()
+                .await;
+
+            for (full_name, result) in full_names.iter().zip(results) {
+                let (releases, repo_watermark) = match result {
+                    Ok(result) => result,
+                    Err(e) => {
+                        log::warn!["failed to fetch the releases of {full_name}: {e}"];
+                        continue;
+                    }
+                };
+                watermarks.insert(full_name.clone(), repo_watermark);
+                for (mut preview, content) in releases {
+                    if utility::db::is_url_known(&mut env.db_conn, &preview.url)? {
+                        continue;
+                    }
+                    preview.source = Some(source.to_owned());
+                    previews.push((preview, content));
+                }
+            }
+
+            let new_watermark = serde_json::to_string(&watermarks)?;
+            if last_watermark.as_ref() != Some(&new_watermark) {
+                watermark = Some(new_watermark);
+            }
+        }
+        FeedSource::Saved => {
+            let content = fs::read_to_string(config::SAVED_URLS_FILEPATH.as_str())?;
+            for url in content.split("\n").filter(|s| !s.is_empty()) {
+                let mut preview = Preview::from_url(url.to_owned());
+                if !utility::db::is_url_known(&mut env.db_conn, &preview.url)? {
+                    preview.saved = true;
+                    previews.push((preview, None));
+                }
+            }
+        }
+    }
+
+    // the same URL may be listed more than once, once canonicalized
+    let mut urls = std::collections::HashSet::new();
+    previews.retain(|(preview, _)| urls.insert(preview.url.clone()));
+
+    Ok((previews, watermark))
+}
+
+/// Loads the stored previews that are published by the feed, newest first.
+pub fn get_feed_previews(env: &mut Env, feed: &FeedConfig) -> Result<Vec<Preview>> {
+    let mut previews = match &feed.source {
+        FeedSource::Rss { source, .. }
+        | FeedSource::HackerNews { source, .. }
+        | FeedSource::Arxiv { source, .. }
+        | FeedSource::GithubReleases { source, .. } => {
+            use crate::schema::previews::dsl;
+
+            let then = chrono::Utc::now()
+                .date_naive()
+                .checked_sub_days(feed.recency_cutoff())
+                .ok_or(anyhow!("invalid recency cutoff for feed {}", feed.name))?;
+
+            dsl::previews
+                .filter(dsl::added_date.gt(then))
+                .filter(dsl::source.eq(source))
+                .select(Preview::as_select())
+                .load(&mut env.db_conn)?
+        }
+        FeedSource::Saved => get_recent_saved_previews(&mut env.db_conn, feed.recency_cutoff())?,
+    };
+    sort_newest_first(&mut previews);
+    Ok(previews)
+}
+
+/// Sorts the previews by when they were published, or else when they were
+/// added, newest first.
+pub fn sort_newest_first(previews: &mut [Preview]) {
+    previews.sort_by(|a, b| {
+        b.published_or_added()
+            .cmp(&a.published_or_added())
+            .then(a.url.cmp(&b.url))
+    });
+}
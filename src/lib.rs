@@ -1,142 +1,144 @@
 use crate::models::Preview;
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use diesel::prelude::*;
-use std::io::Write;
 
 pub mod config;
+pub mod embellisher;
+pub mod feeds;
 pub mod models;
+pub mod pipeline;
 pub mod rss_channel;
 pub mod schema;
+pub mod site;
 pub mod utility;
 
 pub struct Env {
-    pub client: reqwest::Client,
+    pub fetcher: utility::http::Fetcher,
     pub db_conn: diesel::SqliteConnection,
-    pub readability: readability_js::Readability,
-    pub octocrab: octocrab::Octocrab,
+    octocrab: octocrab::Octocrab,
+    pub embellishers: embellisher::Embellishers,
+    pub llm: Box<dyn utility::ai::LlmBackend>,
+    pub database_url: String,
+    pub feeds_dirpath: String,
+    /// When set, nothing is written to the database or to the feeds directory.
+    pub dry_run: bool,
+    /// How many previews are embellished at once.
+    pub concurrency: usize,
+    /// How many previews from the same host are embellished at once.
+    pub per_host_concurrency: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvOptions {
+    pub database_url: String,
+    pub feeds_dirpath: String,
+    pub dry_run: bool,
+    pub concurrency: usize,
+    pub per_host_concurrency: usize,
+    /// Where the responses to GET requests are cached, if anywhere.
+    pub http_cache_dirpath: Option<String>,
+    /// Whether GET requests are only answered from the HTTP cache, and the
+    /// GitHub API and LLM backends other than `mock` aren't used.
+    pub offline: bool,
+}
+
+impl Default for EnvOptions {
+    fn default() -> Self {
+        EnvOptions {
+            database_url: config::DATABASE_URL.to_string(),
+            feeds_dirpath: config::FEEDS_DIRPATH.to_owned(),
+            dry_run: false,
+            concurrency: config::CONCURRENCY,
+            per_host_concurrency: config::PER_HOST_CONCURRENCY,
+            http_cache_dirpath: Some(config::HTTP_CACHE_DIRPATH.to_owned()),
+            offline: false,
+        }
+    }
 }
 
 impl Env {
     pub fn new() -> Result<Self> {
-        let conn = utility::db::establish_connection();
+        Self::with_options(EnvOptions::default())
+    }
 
-        let readability = readability_js::Readability::new()?;
+    pub fn with_options(options: EnvOptions) -> Result<Self> {
+        let mut conn = utility::db::establish_connection(&options.database_url);
+        if !options.dry_run {
+            utility::db::backfill(&mut conn)?;
+        }
 
-        let octocrab = octocrab::Octocrab::builder()
-            .personal_token(config::GITHUB_PERSONAL_ACCESS_TOKEN.as_str())
-            .build()
-            .unwrap();
+        let fetcher = utility::http::Fetcher::with_options(utility::http::FetcherOptions {
+            cache_dirpath: options.http_cache_dirpath,
+            offline: options.offline,
+            ..Default::default()
+        })?;
 
-        let client = reqwest::Client::new();
+        let octocrab = utility::http::build_octocrab(fetcher.options())?;
+
+        let llm = utility::ai::backend_from_config(options.offline)?;
 
         Ok(Env {
             db_conn: conn,
-            readability,
             octocrab,
-            client,
+            fetcher,
+            embellishers: embellisher::Embellishers::default(),
+            llm,
+            database_url: options.database_url,
+            feeds_dirpath: options.feeds_dirpath,
+            dry_run: options.dry_run,
+            concurrency: options.concurrency,
+            per_host_concurrency: options.per_host_concurrency,
         })
     }
+
+    /// The GitHub client, unless in offline mode, since the responses of the
+    /// GitHub API aren't cached.
+    pub fn octocrab(&self) -> Result<&octocrab::Octocrab> {
+        if self.fetcher.options().offline {
+            return Err(anyhow::anyhow!("the GitHub API can't be used offline"));
+        }
+        Ok(&self.octocrab)
+    }
 }
 
-/// Embellishes a preview with basic content, inexpensively.
-pub async fn embellish_preview(env: &mut Env, preview: &mut Preview) -> Result<Option<String>> {
+/// Embellishes a preview with basic content, inexpensively, using the
+/// embellisher registered on `env` that matches its URL. A preview that its
+/// source already embellished, like the result of an arXiv query, is only
+/// finished off.
+pub async fn embellish_preview(
+    env: &Env,
+    preview: &mut Preview,
+) -> Result<Option<embellisher::ExtractedContent>> {
     log::info!["embellish_preview: {}", &preview.url];
 
-    let mut content: Option<String> = None;
-
-    if let Some(arxiv_id) = utility::arxiv::get_id_from_url(&preview.url) {
-        if let Ok(article) = utility::arxiv::fetch_by_id(arxiv_id).await {
-            preview.title = Some(article.title);
-            // NOTE: could use DateTime::parse_from_rfc3339
-            preview.published_date = Some(article.published);
-            if preview.source.is_none() {
-                preview.source = Some("ArXiv".to_owned())
-            }
-            preview.tags = Some(article.category_names.join(", "));
-            preview.summary = Some(article.summary.clone());
-            content = Some(article.summary.clone());
-        } else {
-            log::error!["failed to fetch ArXiv article: {}", preview.url];
+    let content = match env.embellishers.find(&preview.url) {
+        _ if preview.embellished => None,
+        None => {
+            log::warn!["no embellisher matches url: {}", preview.url];
+            None
         }
-    } else if preview.url.starts_with("https://x.com/") {
-        if let Ok(post) = utility::x::fetch_post(&preview.url).await {
-            let html = scraper::Html::parse_fragment(&post.html);
-            let mut text = String::new();
-            for s in html.root_element().text() {
-                text.push_str(&format!(" {s}"));
-            }
-            preview.summary = Some(text.chars().take(config::MAX_CHARS_SUMMARY).collect());
-            content = Some(text.clone());
-        } else {
-            log::error!["failed to fetch X post: {}", preview.url];
+        Some(embellisher) => {
+            log::trace![
+                "embellish_preview: using {} embellisher",
+                embellisher.name()
+            ];
+            embellisher.embellish(env, preview).await?
         }
-    } else if preview.url.starts_with("https://github.com") {
-        if let Ok(info) = utility::github::fetch_repo_info(&env.octocrab, &preview.url).await {
-            content = info.readme.clone();
-            preview.summary = info
-                .readme
-                .map(|s| s.chars().take(config::MAX_CHARS_SUMMARY).collect());
-        } else {
-            log::error!["failed to fetch GitHub repo info: {}", preview.url];
-        }
-    } else {
-        // fetch content at URL
-        let response = env.client.get(&preview.url).send().await?;
-        let headers = response.headers();
-
-        let content_type = match headers.get("content-type") {
-            None => {
-                return Result::Err(anyhow!(
-                    "I failed to get the content type, since the response does not have a header for content-type: {response:?}"
-                ));
-            }
-            Some(content_type) => {
-                let bytes = content_type.as_bytes();
-                let str = String::from_utf8_lossy(bytes);
-                str.to_string()
-            }
-        };
-
-        // extract content
-        #[allow(clippy::single_match)]
-        match content_type.as_str() {
-            "text/pdf" => {
-                let mut file = tempfile::Builder::new().suffix(".pdf").tempfile()?;
-                let bytes = response.bytes().await?;
-                file.write_all(&bytes)?;
-                let file_path = file
-                    .path()
-                    .to_str()
-                    .ok_or(anyhow!("failed to convert file path to String"))?;
-                let text = pdf_extract::extract_text(file_path)?;
-                content = Some(text);
-            }
-            content_type if content_type.starts_with("text/html") => {
-                let html = response.text().await?;
-                match env.readability.parse_with_url(&html, &preview.url) {
-                    Err(e) => {
-                        log::warn!["failed to use Readability to parse with url: {e}"];
-                        preview.title = Some(preview.url.clone());
-                    }
-                    Ok(article) => {
-                        // let content = &article.text_content;
-                        // let byline = &article.byline;
-                        // let published_date = &article.published_time;
-                        // let title = &article.title;
-
-                        preview.title = Some(article.title.clone());
-                        if let Some(pub_date) = article.published_time {
-                            preview.published_date = Some(pub_date);
-                        }
-
-                        content = Some(article.text_content.clone());
-                    }
-                }
-            }
-            // TODO: handle other types of content
-            _ => {
-                log::warn!("unrecognized content type: {content_type}");
-            }
+    };
+
+    // record the discussion of a preview that came from a Hacker News feed
+    if preview.score.is_none()
+        && let Some(id) = preview
+            .discussion_url
+            .as_deref()
+            .and_then(utility::hackernews::get_item_id_from_url)
+    {
+        match utility::hackernews::fetch_item(&env.fetcher, utility::hackernews::api_url(), id)
+            .await
+        {
+            Ok(Some(item)) => item.apply_to(preview),
+            Ok(None) => log::warn!["no such Hacker News item: {id}"],
+            Err(e) => log::warn!["failed to fetch Hacker News item {id}: {e}"],
         }
     }
 
@@ -144,7 +146,13 @@ pub async fn embellish_preview(env: &mut Env, preview: &mut Preview) -> Result<O
     if preview.summary.is_none()
         && let Some(content) = &content
     {
-        preview.summary = Some(content.chars().take(config::MAX_CHARS_SUMMARY).collect());
+        preview.summary = Some(
+            content
+                .text
+                .chars()
+                .take(config::MAX_CHARS_SUMMARY)
+                .collect(),
+        );
     }
 
     // if still no summary, use title as summary
@@ -166,22 +174,25 @@ pub async fn embellish_preview(env: &mut Env, preview: &mut Preview) -> Result<O
         *summary = summary.trim().to_string();
     }
 
+    preview.embellished = true;
+
     Ok(content)
 }
 
 /// Requires input preview to already be embellished.
-pub async fn bookmark_preview(_env: &mut Env, preview: &mut Preview) -> Result<()> {
+pub async fn bookmark_preview(env: &mut Env, preview: &mut Preview) -> Result<()> {
     log::info!["bookmark_preview: {}", &preview.url];
 
     // generate tags
     if preview.tags.is_none()
         && let Some(summary) = &preview.summary
     {
-        let response = utility::ai::gemini_cli(&format!(
+        let response = env.llm.complete(&format!(
             "Consider the following content:\n\n{summary}...\n\nWrite a comma-separated list of categorizational tags for the above content. Respond ONLY with the comma-separated list"
-        ));
+        )).await;
         if let Ok(response) = response {
-            preview.tags = Some(response);
+            preview.tags = Some(response.trim().to_owned());
+            preview.tags_provenance = Some(models::TagProvenance::Ai.to_string());
         }
     }
 
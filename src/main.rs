@@ -0,0 +1,602 @@
+use anyhow::{Result, anyhow};
+use clap::{Parser, Subcommand};
+use dotenvy::dotenv;
+use linkstitcher::{
+    Env, EnvOptions, bookmark_preview, config, embellish_preview,
+    embellisher::ExtractedContent,
+    feeds,
+    models::{Preview, PreviewContent, TagProvenance},
+    site,
+    utility::{self, citation::CitationFormat},
+};
+use std::fs;
+
+/// Stitches links from bookmarks, saved URLs and upstream feeds into RSS feeds.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// The SQLite database to use, instead of $DATABASE_URL.
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+
+    /// The directory that feeds are written to.
+    #[arg(long, global = true, default_value = config::FEEDS_DIRPATH)]
+    feeds_dir: String,
+
+    /// The log level, instead of the one set by $RUST_LOG.
+    #[arg(long, global = true)]
+    log_level: Option<log::LevelFilter>,
+
+    /// Don't write anything to the database or to the feeds directory.
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// How many previews are embellished at once.
+    #[arg(long, global = true, default_value_t = config::CONCURRENCY)]
+    concurrency: usize,
+
+    /// How many previews from the same host are embellished at once.
+    #[arg(long, global = true, default_value_t = config::PER_HOST_CONCURRENCY)]
+    per_host_concurrency: usize,
+
+    /// The directory that the responses to GET requests are cached in.
+    #[arg(long, global = true, default_value = config::HTTP_CACHE_DIRPATH)]
+    http_cache_dir: String,
+
+    /// Don't cache the responses to GET requests.
+    #[arg(long, global = true, conflicts_with = "offline")]
+    no_http_cache: bool,
+
+    /// Only answer GET requests from the HTTP cache, to replay a run, without
+    /// sending any other request, to the GitHub API or an LLM backend either.
+    /// Use with LLM_BACKEND=mock to replay it deterministically.
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// When adding, saving or bookmarking URLs, also add the pages that they
+    /// link out to, like the links in an X post, as previews of their own.
+    #[arg(long, global = true)]
+    enqueue_links: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Embellishes and stores URLs.
+    Add {
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Embellishes, stores and marks URLs as saved. Without any URLs, the URLs
+    /// in $SAVED_URLS_FILEPATH are used, and then the file is cleared.
+    Save { urls: Vec<String> },
+    /// Embellishes, stores and bookmarks URLs. Without any URLs, the URLs in
+    /// $BOOKMARKED_URLS_FILEPATH are used, and then the file is cleared.
+    Bookmark { urls: Vec<String> },
+    /// Embellishes URLs and prints the results, without storing them.
+    Embellish {
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Fetches the named feeds from the feeds file, or all of them.
+    FetchFeed { names: Vec<String> },
+    /// Lists stored previews.
+    List {
+        /// Only list saved previews.
+        #[arg(long)]
+        saved: bool,
+        /// Only list bookmarked previews.
+        #[arg(long)]
+        bookmarked: bool,
+    },
+    /// Searches stored previews by their title, summary, tags and content.
+    Search {
+        /// An FTS5 query, e.g. `"type theory" OR hask*`.
+        query: String,
+        /// Only search saved previews.
+        #[arg(long)]
+        saved: bool,
+        /// Only search bookmarked previews.
+        #[arg(long)]
+        bookmarked: bool,
+        /// Only search previews from this source.
+        #[arg(long)]
+        source: Option<String>,
+        /// The maximum number of results.
+        #[arg(long, default_value_t = utility::search::DEFAULT_SEARCH_LIMIT)]
+        limit: i64,
+    },
+    /// Shows a stored preview.
+    Show {
+        url: String,
+        /// Also print the full extracted content.
+        #[arg(long)]
+        content: bool,
+    },
+    /// Manages the tags of stored previews.
+    #[command(subcommand)]
+    Tags(TagsCommand),
+    /// Deletes stored previews.
+    Delete {
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Prints stored previews as JSON.
+    Export {
+        /// Only export saved previews.
+        #[arg(long)]
+        saved: bool,
+        /// Only export bookmarked previews.
+        #[arg(long)]
+        bookmarked: bool,
+    },
+    /// Writes the named feeds from the feeds file, or all of them, using only
+    /// stored previews.
+    Render { names: Vec<String> },
+    /// Writes the HTML site for every feed in the feeds file, using only
+    /// stored previews.
+    Site,
+    /// Rewrites the stored URLs into their canonical form, merging previews
+    /// whose URLs turn out to be the same.
+    Canonicalize,
+    /// Parses the published date of every stored preview into its published
+    /// time, e.g. after the date parser has learned a new format.
+    BackfillDates,
+    /// Prints the citations of the given arXiv previews, or else of every
+    /// stored preview that is an arXiv paper.
+    Cite {
+        urls: Vec<String>,
+        /// Only cite saved previews.
+        #[arg(long)]
+        saved: bool,
+        /// Only cite bookmarked previews.
+        #[arg(long)]
+        bookmarked: bool,
+        #[arg(long, value_enum, default_value_t = CiteFormat::Bibtex)]
+        format: CiteFormat,
+    },
+    /// Checks the bookmarked arXiv papers for new versions, and prints the ones
+    /// that have one.
+    CheckArxivVersions,
+}
+
+#[derive(Debug, Subcommand)]
+enum TagsCommand {
+    /// Lists every tag with the number of previews that have it.
+    List,
+    /// Lists the previews that have a tag.
+    Show { tag: String },
+    /// Adds tags to a preview.
+    Add {
+        url: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Removes a tag from a preview.
+    Remove { url: String, tag: String },
+    /// Renames a tag, merging it into the tag with the new name if there is one.
+    Rename { from: String, to: String },
+    /// Merges tags into another tag.
+    Merge {
+        into: String,
+        #[arg(required = true)]
+        froms: Vec<String>,
+    },
+}
+
+/// The formats that `cite` prints citations in.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum CiteFormat {
+    Bibtex,
+    CslJson,
+}
+
+impl From<CiteFormat> for CitationFormat {
+    fn from(format: CiteFormat) -> Self {
+        match format {
+            CiteFormat::Bibtex => CitationFormat::Bibtex,
+            CiteFormat::CslJson => CitationFormat::CslJson,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // before the logger, so that RUST_LOG can be set in .env
+    dotenv()?;
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(log_level) = cli.log_level {
+        logger.filter_level(log_level);
+    }
+    logger.init();
+
+    log::trace!("linkstitcher::main");
+
+    let mut env = Env::with_options(EnvOptions {
+        database_url: cli
+            .database_url
+            .unwrap_or_else(|| config::DATABASE_URL.to_string()),
+        feeds_dirpath: cli.feeds_dir,
+        dry_run: cli.dry_run,
+        concurrency: cli.concurrency,
+        per_host_concurrency: cli.per_host_concurrency,
+        http_cache_dirpath: (!cli.no_http_cache).then_some(cli.http_cache_dir),
+        offline: cli.offline,
+    })?;
+
+    match cli.command {
+        Command::Add { urls } => {
+            for url in urls {
+                let (preview, content) = load_or_embellish(&mut env, &url).await?;
+                let links = links_of(&content, cli.enqueue_links);
+                store_preview(&mut env, &preview, content)?;
+                add_links(&mut env, links).await?;
+            }
+        }
+        Command::Save { urls } => {
+            let (urls, from_file) = urls_or_from_file(urls, &config::SAVED_URLS_FILEPATH)?;
+            for url in urls {
+                let (mut preview, content) = load_or_embellish(&mut env, &url).await?;
+                let links = links_of(&content, cli.enqueue_links);
+                preview.saved = true;
+                store_preview(&mut env, &preview, content)?;
+                add_links(&mut env, links).await?;
+            }
+            if from_file {
+                clear_urls_file(&env, &config::SAVED_URLS_FILEPATH)?;
+            }
+        }
+        Command::Bookmark { urls } => {
+            let (urls, from_file) = urls_or_from_file(urls, &config::BOOKMARKED_URLS_FILEPATH)?;
+            for url in urls {
+                let (mut preview, content) = load_or_embellish(&mut env, &url).await?;
+                let links = links_of(&content, cli.enqueue_links);
+                bookmark_preview(&mut env, &mut preview).await?;
+                store_preview(&mut env, &preview, content)?;
+                add_links(&mut env, links).await?;
+            }
+            if from_file {
+                clear_urls_file(&env, &config::BOOKMARKED_URLS_FILEPATH)?;
+            }
+        }
+        Command::Embellish { urls } => {
+            for url in urls {
+                let mut preview = Preview::from_url(url);
+                let content = embellish_preview(&env, &mut preview)
+                    .await
+                    .unwrap_or_else(|e| {
+                        println!("{e}");
+                        None
+                    });
+                println!("------------------------------------------------");
+                println!("{preview:#?}");
+                if let Some(content) = content {
+                    println!(
+                        "content: {} bytes of {:?} extracted by {}",
+                        content.text.len(),
+                        content.content_type,
+                        content.extraction_method
+                    );
+                    if let Some(page_count) = content.page_count {
+                        println!("pages: {page_count}");
+                    }
+                }
+            }
+        }
+        Command::FetchFeed { names } => {
+            let feeds = feeds::FeedsConfig::load(config::FEEDS_CONFIG_FILEPATH)?.select(&names)?;
+            feeds::run_feeds(&mut env, &feeds).await?;
+        }
+        Command::List { saved, bookmarked } => {
+            for preview in load_previews(&mut env, saved, bookmarked)? {
+                println!("- {:?}: {:?}", preview.url, preview.tags());
+            }
+        }
+        Command::Search {
+            query,
+            saved,
+            bookmarked,
+            source,
+            limit,
+        } => {
+            let filters = utility::search::SearchFilters {
+                saved: saved.then_some(true),
+                bookmarked: bookmarked.then_some(true),
+                source,
+                limit: Some(limit),
+            };
+            for result in utility::search::search_previews(&mut env.db_conn, &query, &filters)? {
+                println!("- {} ({:.4})", result.preview.url, result.rank);
+                if let Some(title) = &result.preview.title {
+                    println!("  {title}");
+                }
+                println!("  {}", result.snippet.replace("\n", " "));
+            }
+        }
+        Command::Show { url, content } => {
+            let url = utility::canonical::canonicalize_url(&url);
+            let preview = utility::db::get_preview(&mut env.db_conn, url.clone())?
+                .ok_or(anyhow!("unknown url: {url}"))?;
+            println!("{preview:#?}");
+            if content {
+                match utility::db::get_preview_content(&mut env.db_conn, &url)? {
+                    None => println!("no stored content"),
+                    Some(content) => {
+                        println!("------------------------------------------------");
+                        println!(
+                            "{} bytes of {:?} extracted by {} at {}",
+                            content.byte_length,
+                            content.content_type,
+                            content.extraction_method,
+                            content.fetched_at
+                        );
+                        if let Some(page_count) = content.page_count {
+                            println!("pages: {page_count}");
+                        }
+                        println!("{}", content.content);
+                    }
+                }
+            }
+        }
+        Command::Tags(command) => run_tags_command(&mut env, command)?,
+        Command::Delete { urls } => {
+            for url in urls {
+                let url = utility::canonical::canonicalize_url(&url);
+                if env.dry_run {
+                    log::info!["dry run: would delete {url}"];
+                } else if !utility::db::delete_preview(&mut env.db_conn, &url)? {
+                    log::warn!["unknown url: {url}"];
+                }
+            }
+        }
+        Command::Export { saved, bookmarked } => {
+            let previews = load_previews(&mut env, saved, bookmarked)?;
+            println!("{}", serde_json::to_string_pretty(&previews)?);
+        }
+        Command::Render { names } => {
+            let feeds = feeds::FeedsConfig::load(config::FEEDS_CONFIG_FILEPATH)?.select(&names)?;
+            for feed in &feeds {
+                feeds::render_feed(&mut env, feed)?;
+            }
+        }
+        Command::Site => {
+            let feeds = feeds::FeedsConfig::load(config::FEEDS_CONFIG_FILEPATH)?.select(&[])?;
+            site::render_site(&mut env, &feeds)?;
+        }
+        Command::Canonicalize => {
+            let count =
+                utility::canonical::merge_duplicate_previews(&mut env.db_conn, env.dry_run)?;
+            log::info!["canonicalized {count} previews"];
+        }
+        Command::Cite {
+            urls,
+            saved,
+            bookmarked,
+            format,
+        } => {
+            let previews = if urls.is_empty() {
+                load_previews(&mut env, saved, bookmarked)?
+            } else {
+                urls.iter()
+                    .map(|url| Preview::from_url(url.clone()))
+                    .collect()
+            };
+            let papers = utility::arxiv_papers::load_papers(&mut env, &previews).await?;
+            print!("{}", CitationFormat::from(format).render(&papers)?);
+        }
+        Command::CheckArxivVersions => {
+            let previews = load_previews(&mut env, false, true)?;
+            for new_version in
+                utility::arxiv_papers::check_new_versions(&mut env, &previews).await?
+            {
+                println!(
+                    "{}: v{} -> v{}",
+                    new_version.paper.url(),
+                    new_version.stored_version,
+                    new_version.paper.version
+                );
+            }
+        }
+        Command::BackfillDates => {
+            let count = utility::date::backfill_published_at(&mut env.db_conn, env.dry_run)?;
+            log::info!["backfilled the published time of {count} previews"];
+        }
+    }
+
+    Ok(())
+}
+
+fn run_tags_command(env: &mut Env, command: TagsCommand) -> Result<()> {
+    if env.dry_run && !matches!(command, TagsCommand::List | TagsCommand::Show { .. }) {
+        log::info!["dry run: would run {command:?}"];
+        return Ok(());
+    }
+
+    match command {
+        TagsCommand::List => {
+            for tag in utility::tags::list_tags(&mut env.db_conn)? {
+                println!("- {}: {}", tag.name, tag.count);
+            }
+        }
+        TagsCommand::Show { tag } => {
+            for preview in utility::tags::get_previews_by_tag(&mut env.db_conn, &tag)? {
+                println!("- {:?}: {:?}", preview.url, preview.title);
+            }
+        }
+        TagsCommand::Add { url, tags } => {
+            let url = utility::canonical::canonicalize_url(&url);
+            if !utility::db::is_url_known(&mut env.db_conn, &url)? {
+                return Err(anyhow!("unknown url: {url}"));
+            }
+            utility::tags::add_preview_tags(&mut env.db_conn, &url, &tags, TagProvenance::Manual)?;
+        }
+        TagsCommand::Remove { url, tag } => {
+            let url = utility::canonical::canonicalize_url(&url);
+            if !utility::tags::remove_preview_tag(&mut env.db_conn, &url, &tag)? {
+                log::warn!["{url} doesn't have the tag: {tag}"];
+            }
+        }
+        TagsCommand::Rename { from, to } => {
+            utility::tags::rename_tag(&mut env.db_conn, &from, &to)?;
+        }
+        TagsCommand::Merge { into, froms } => {
+            utility::tags::merge_tags(&mut env.db_conn, &froms, &into)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the stored preview for `url` if there is one, and embellishes it if it
+/// isn't already. Also returns the content extracted while embellishing.
+async fn load_or_embellish(
+    env: &mut Env,
+    url: &str,
+) -> Result<(Preview, Option<ExtractedContent>)> {
+    let url = utility::canonical::canonicalize_url(url);
+    let existing_preview = utility::db::get_preview(&mut env.db_conn, url.clone())?;
+    let mut preview = existing_preview
+        .clone()
+        .unwrap_or_else(|| Preview::from_url(url.clone()));
+    let mut content = None;
+    if !preview.embellished {
+        match embellish_preview(env, &mut preview).await {
+            Ok(c) => content = c,
+            Err(e) => log::error!("Error during embellish_preview: {e}"),
+        }
+    }
+
+    // embellishing may have rewritten the URL, e.g. into the page that a Hacker
+    // News item links to, or into the canonical URL of a page
+    if env.dry_run {
+        if preview.url != url
+            && let Some(stored) = utility::db::get_preview(&mut env.db_conn, preview.url.clone())?
+        {
+            log::info!["dry run: would merge {url} into {}", stored.url];
+            return Ok((stored, content));
+        }
+        return Ok((preview, content));
+    }
+    let preview = utility::canonical::reconcile_rewritten_url(
+        &mut env.db_conn,
+        &url,
+        existing_preview.as_ref(),
+        preview,
+    )?;
+
+    Ok((preview, content))
+}
+
+/// The links of the content that are to be added as previews of their own, if
+/// any are.
+fn links_of(content: &Option<ExtractedContent>, enqueue_links: bool) -> Vec<String> {
+    match content {
+        Some(content) if enqueue_links => content.links.clone(),
+        _ => vec![],
+    }
+}
+
+/// Adds the pages that links lead to as previews, without following their own
+/// links in turn.
+async fn add_links(env: &mut Env, links: Vec<String>) -> Result<()> {
+    for link in links {
+        let url = utility::x::resolve_link(&env.fetcher, &link).await;
+        log::info!["adding linked page: {url}"];
+        let (preview, content) = load_or_embellish(env, &url).await?;
+        store_preview(env, &preview, content)?;
+    }
+    Ok(())
+}
+
+fn store_preview(
+    env: &mut Env,
+    preview: &Preview,
+    content: Option<ExtractedContent>,
+) -> Result<()> {
+    if env.dry_run {
+        log::info!["dry run: would store {}", preview.url];
+        return Ok(());
+    }
+    utility::db::insert_or_update_preview(&mut env.db_conn, preview)?;
+    if let Some(mut content) = content {
+        if let Some(paper) = content.arxiv_paper.take() {
+            utility::db::upsert_arxiv_paper(&mut env.db_conn, &paper)?;
+        }
+        if let Some(repo) = content.github_repo.take() {
+            utility::db::upsert_github_repo(&mut env.db_conn, &repo)?;
+        }
+        if !content.text.is_empty() {
+            utility::db::insert_or_update_preview_content(
+                &mut env.db_conn,
+                &PreviewContent::new(preview.url.clone(), content),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn load_previews(env: &mut Env, saved: bool, bookmarked: bool) -> Result<Vec<Preview>> {
+    Ok(utility::db::get_all_previews(&mut env.db_conn)?
+        .into_iter()
+        .filter(|preview| !saved || preview.saved)
+        .filter(|preview| !bookmarked || preview.bookmarked)
+        .collect())
+}
+
+/// Returns `urls`, or the URLs listed in the file if there are none. Also
+/// returns whether the URLs were read from the file.
+fn urls_or_from_file(urls: Vec<String>, file_path: &str) -> Result<(Vec<String>, bool)> {
+    if !urls.is_empty() {
+        return Ok((urls, false));
+    }
+    let content = fs::read_to_string(file_path)?;
+    let urls = content
+        .split("\n")
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_owned())
+        .collect();
+    Ok((urls, true))
+}
+
+fn clear_urls_file(env: &Env, file_path: &str) -> Result<()> {
+    if env.dry_run {
+        log::info!["dry run: would clear {file_path}"];
+        return Ok(());
+    }
+    fs::write(file_path, String::new())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verifies_the_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_global_flags_after_the_subcommand() {
+        let cli = Cli::try_parse_from([
+            "linkstitcher",
+            "add",
+            "https://example.com/",
+            "--dry-run",
+            "--database-url",
+            "test.db",
+        ])
+        .unwrap();
+        assert!(cli.dry_run);
+        assert_eq!(cli.database_url.as_deref(), Some("test.db"));
+        assert!(matches!(cli.command, Command::Add { urls } if urls == ["https://example.com/"]));
+
+        // add needs a URL
+        assert!(Cli::try_parse_from(["linkstitcher", "add"]).is_err());
+    }
+}
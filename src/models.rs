@@ -1,9 +1,9 @@
-use crate::schema::*;
+use crate::{embellisher::ExtractedContent, schema::*, utility};
 use anyhow::{Result, anyhow};
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use diesel::{prelude::*, sqlite};
 
-#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[derive(Debug, Clone, Queryable, QueryableByName, Selectable, Insertable, serde::Serialize)]
 #[diesel(table_name = previews)]
 #[diesel(check_for_backend(sqlite::Sqlite))]
 pub struct Preview {
@@ -16,9 +16,27 @@ pub struct Preview {
     // optional
     pub source: Option<String>,
     pub title: Option<String>,
-    pub published_date: Option<String>,
+    /// The published date as the source gave it. It is private, so that it
+    /// can only be set with [`Preview::set_published_date`], which keeps
+    /// `published_at` in sync.
+    published_date: Option<String>,
     pub tags: Option<String>,
     pub summary: Option<String>,
+    /// Where `tags` came from, as a [`TagProvenance`].
+    pub tags_provenance: Option<String>,
+    pub author: Option<String>,
+    /// The name of the site that the preview is on, e.g. `The Verge`.
+    pub site_name: Option<String>,
+    pub image_url: Option<String>,
+    /// The published date in UTC, if `published_date` could be parsed.
+    pub published_at: Option<NaiveDateTime>,
+    /// The score of the discussion of the preview, e.g. its points on Hacker
+    /// News.
+    pub score: Option<i32>,
+    pub comment_count: Option<i32>,
+    /// The username of whoever submitted the preview to the discussion.
+    pub submitter: Option<String>,
+    pub discussion_url: Option<String>,
 }
 
 impl Preview {
@@ -28,20 +46,48 @@ impl Preview {
             .map(|tags| tags.split(",").map(|s| s.trim()).collect())
     }
 
+    /// The published date as the source gave it.
+    pub fn published_date(&self) -> Option<&str> {
+        self.published_date.as_deref()
+    }
+
+    /// Sets the published date, and the published time parsed from it.
+    pub fn set_published_date(&mut self, published_date: Option<String>) {
+        self.published_at = published_date
+            .as_deref()
+            .and_then(utility::date::parse_date)
+            .map(|time| time.naive_utc());
+        self.published_date = published_date;
+    }
+
+    /// When the preview was published, or else when it was added.
+    pub fn published_or_added(&self) -> DateTime<Utc> {
+        match self.published_at {
+            Some(time) => time.and_utc(),
+            None => self.added_date.and_time(chrono::NaiveTime::MIN).and_utc(),
+        }
+    }
+
     pub fn from_rss_item(source: String, item: rss::Item) -> Result<Preview> {
-        Ok(Preview {
+        let published_date = item.pub_date.clone();
+        let mut preview = Preview {
             url: match &item.link {
                 None => {
                     return Err(anyhow!(
                         "I can't get the url of this rss item since it doesn't have one: {item:?}"
                     ));
                 }
-                Some(url) => url.to_owned(),
+                Some(url) => utility::canonical::canonicalize_url(url),
             },
             added_date: chrono::Utc::now().date_naive(),
             title: item.title,
             source: Some(source.to_string()),
-            published_date: item.pub_date,
+            published_date: None,
+            published_at: None,
+            score: None,
+            comment_count: None,
+            submitter: None,
+            discussion_url: item.comments,
             tags: {
                 let cs: Vec<_> = item
                     .categories
@@ -54,22 +100,39 @@ impl Preview {
                     Some(cs.join(", "))
                 }
             },
+            tags_provenance: (!item.categories.is_empty()).then(|| TagProvenance::Rss.to_string()),
             summary: item.description,
+            author: item.author.or(item
+                .dublin_core_ext
+                .and_then(|dc| dc.creators.first().cloned())),
+            site_name: None,
+            image_url: None,
             bookmarked: false,
             embellished: false,
             saved: false,
-        })
+        };
+        preview.set_published_date(published_date);
+        Ok(preview)
     }
 
     pub fn from_url(url: String) -> Self {
         Self {
-            url,
+            url: utility::canonical::canonicalize_url(&url),
             added_date: chrono::Utc::now().date_naive(),
             title: None,
             source: None,
             published_date: None,
+            published_at: None,
+            score: None,
+            comment_count: None,
+            submitter: None,
+            discussion_url: None,
             tags: None,
+            tags_provenance: None,
             summary: None,
+            author: None,
+            site_name: None,
+            image_url: None,
             bookmarked: false,
             embellished: false,
             saved: false,
@@ -77,12 +140,210 @@ impl Preview {
     }
 }
 
+/// The full content extracted from the URL of a preview.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = preview_contents)]
+#[diesel(check_for_backend(sqlite::Sqlite))]
+pub struct PreviewContent {
+    pub url: String,
+    pub content: String,
+    pub content_type: Option<String>,
+    pub byte_length: i64,
+    pub extraction_method: String,
+    pub fetched_at: NaiveDateTime,
+    /// The number of pages of paged content, such as PDFs.
+    pub page_count: Option<i32>,
+}
+
+impl PreviewContent {
+    pub fn new(url: String, content: ExtractedContent) -> Self {
+        PreviewContent {
+            url,
+            byte_length: content.text.len() as i64,
+            content: content.text,
+            content_type: content.content_type,
+            extraction_method: content.extraction_method,
+            fetched_at: chrono::Utc::now().naive_utc(),
+            page_count: content.page_count,
+        }
+    }
+}
+
+/// The metadata of an arXiv paper, as of its latest version that has been seen.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, serde::Serialize)]
+#[diesel(table_name = arxiv_papers)]
+#[diesel(check_for_backend(sqlite::Sqlite))]
+pub struct ArxivPaper {
+    /// The id without a version, e.g. `2410.12345`.
+    pub arxiv_id: String,
+    pub version: i32,
+    pub title: String,
+    /// A JSON array of the names of the authors. See [`ArxivPaper::authors`].
+    pub authors: String,
+    pub summary: String,
+    pub published: Option<String>,
+    pub updated: Option<String>,
+    pub comment: Option<String>,
+    pub pdf_url: Option<String>,
+    pub primary_category: Option<String>,
+    /// Comma-separated category codes, e.g. `cs.PL, cs.LO`.
+    pub categories: String,
+    pub fetched_at: NaiveDateTime,
+}
+
+impl ArxivPaper {
+    pub fn from_arxiv(article: &utility::arxiv::Arxiv) -> Self {
+        let (arxiv_id, version) = utility::arxiv::split_version(&article.id);
+        let non_empty = |s: &str| (!s.trim().is_empty()).then(|| s.trim().to_owned());
+        ArxivPaper {
+            arxiv_id: arxiv_id.to_owned(),
+            version: version.unwrap_or(1),
+            title: article
+                .title
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            authors: serde_json::to_string(&article.authors).unwrap_or("[]".to_owned()),
+            summary: article.summary.trim().to_owned(),
+            published: non_empty(&article.published),
+            updated: non_empty(&article.updated),
+            comment: article.comment.as_deref().and_then(non_empty),
+            pdf_url: non_empty(&article.pdf_url),
+            primary_category: non_empty(&article.primary_category),
+            categories: article.categories.join(", "),
+            fetched_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    pub fn authors(&self) -> Vec<String> {
+        serde_json::from_str(&self.authors).unwrap_or_default()
+    }
+
+    pub fn url(&self) -> String {
+        format!("https://arxiv.org/abs/{}", self.arxiv_id)
+    }
+}
+
+/// The metadata of a GitHub repository, as of when it was last embellished.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable, serde::Serialize)]
+#[diesel(table_name = github_repos)]
+#[diesel(check_for_backend(sqlite::Sqlite))]
+pub struct GithubRepo {
+    /// `owner/name`, e.g. `rust-lang/rust`.
+    pub full_name: String,
+    pub owner: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub stars: Option<i32>,
+    pub forks: Option<i32>,
+    pub language: Option<String>,
+    /// Comma-separated topics, e.g. `cli, rss`.
+    pub topics: String,
+    /// The SPDX id of the license, e.g. `MIT`.
+    pub license: Option<String>,
+    pub pushed_at: Option<NaiveDateTime>,
+    pub archived: bool,
+    pub fetched_at: NaiveDateTime,
+}
+
+/// How far a feed has read its source, e.g. the published time of the newest
+/// arXiv paper that it has seen.
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = feed_state)]
+#[diesel(check_for_backend(sqlite::Sqlite))]
+pub struct FeedState {
+    pub feed_name: String,
+    pub watermark: String,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = tags)]
+#[diesel(check_for_backend(sqlite::Sqlite))]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = preview_tags)]
+#[diesel(check_for_backend(sqlite::Sqlite))]
+pub struct PreviewTag {
+    pub preview_url: String,
+    pub tag_id: i32,
+    pub provenance: String,
+}
+
+/// Where a tag of a preview came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagProvenance {
+    /// The categories of an upstream RSS item.
+    Rss,
+    /// The categories of an arXiv paper.
+    Arxiv,
+    /// The topics of a GitHub repository.
+    Github,
+    /// Generated by an LLM.
+    Ai,
+    /// Added by hand.
+    Manual,
+    /// Backfilled from before provenance was recorded.
+    Legacy,
+}
+
+impl std::fmt::Display for TagProvenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            TagProvenance::Rss => "rss",
+            TagProvenance::Arxiv => "arxiv",
+            TagProvenance::Github => "github",
+            TagProvenance::Ai => "ai",
+            TagProvenance::Manual => "manual",
+            TagProvenance::Legacy => "legacy",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl std::str::FromStr for TagProvenance {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rss" => Ok(TagProvenance::Rss),
+            "arxiv" => Ok(TagProvenance::Arxiv),
+            "github" => Ok(TagProvenance::Github),
+            "ai" => Ok(TagProvenance::Ai),
+            "manual" => Ok(TagProvenance::Manual),
+            "legacy" => Ok(TagProvenance::Legacy),
+            _ => Err(anyhow!("unknown tag provenance: {s}")),
+        }
+    }
+}
+
 impl From<Preview> for rss::Item {
     fn from(val: Preview) -> Self {
+        let pub_date = val.published_or_added().to_rfc2822();
+        let categories = val
+            .tags()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| rss::Category {
+                name: tag.to_owned(),
+                domain: None,
+            })
+            .collect::<Vec<_>>();
         rss::ItemBuilder::default()
+            .guid(rss::Guid {
+                value: val.url.clone(),
+                permalink: true,
+            })
+            .categories(categories)
             .link(val.url)
+            .comments(val.discussion_url)
             .title(val.title)
-            .pub_date(val.added_date.format("%Y-%m-%d").to_string())
+            .pub_date(pub_date)
             .description(val.summary)
             .build()
     }
@@ -0,0 +1,272 @@
+//! Embellishing, filtering and storing many previews concurrently.
+//!
+//! At most [`Env::concurrency`] previews are processed at once, and at most
+//! [`Env::per_host_concurrency`] of them are fetched from the same host at
+//! once. Previews are processed on the current task, since embellishers aren't
+//! `Send`, so the concurrency only overlaps their waiting on the network and on
+//! the LLM. Stored previews are queued on a [`DbWriter`].
+use crate::{
+    Env, embellish_preview,
+    embellisher::ExtractedContent,
+    models::{Preview, PreviewContent},
+    rss_channel::SmartFilter,
+    utility::db::{DbWrite, DbWriter},
+};
+use anyhow::{Result, anyhow};
+use futures::{StreamExt, stream};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limits how many requests are made to the same host at once.
+pub struct HostLimiter {
+    per_host: usize,
+    semaphores: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimiter {
+    pub fn new(per_host: usize) -> Self {
+        HostLimiter {
+            per_host: per_host.max(1),
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits until a request can be made to the host of `url`, which is allowed
+    /// until the returned permit is dropped.
+    pub async fn acquire(&self, url: &str) -> Result<OwnedSemaphorePermit> {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+            .unwrap_or_default();
+        let semaphore = self
+            .semaphores
+            .lock()
+            .map_err(|_| anyhow!("the host limiter is poisoned"))?
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.per_host)))
+            .clone();
+        Ok(semaphore.acquire_owned().await?)
+    }
+}
+
+/// Embellishes and filters the previews concurrently, and queues the ones that
+/// pass the filter on `writer`, if there is one. Each preview may come with the
+/// content that its source already extracted. Returns the previews that passed
+/// the filter, in their original order.
+pub async fn process_previews(
+    env: &Env,
+    filter: &SmartFilter,
+    previews: Vec<(Preview, Option<ExtractedContent>)>,
+    writer: Option<&DbWriter>,
+) -> Vec<Preview> {
+    let limiter = HostLimiter::new(env.per_host_concurrency);
+
+    let mut kept = stream::iter(previews.into_iter().enumerate())
+        .map(|(i, (preview, content))| {
+            let limiter = &limiter;
+            async move {
+                let (preview, mut content) =
+                    embellish_limited(env, limiter, preview, content).await;
+                match filter.checked(env.llm.as_ref(), preview).await {
+                    Ok((true, preview)) => {
+                        if let Some(writer) = writer {
+                            let arxiv_paper = content
+                                .as_mut()
+                                .and_then(|content| content.arxiv_paper.take());
+                            let github_repo = content
+                                .as_mut()
+                                .and_then(|content| content.github_repo.take());
+                            let content = content
+                                .filter(|content| !content.text.is_empty())
+                                .map(|content| PreviewContent::new(preview.url.clone(), content));
+                            let mut writes = vec![DbWrite::InsertPreview(preview.clone(), content)];
+                            writes.extend(arxiv_paper.map(DbWrite::UpsertArxivPaper));
+                            writes.extend(github_repo.map(DbWrite::UpsertGithubRepo));
+                            for write in writes {
+                                if let Err(e) = writer.write(write) {
+                                    log::warn!["Error during DbWriter::write: {e}"];
+                                }
+                            }
+                        }
+                        Some((i, preview))
+                    }
+                    Ok((false, preview)) => {
+                        log::trace!["filtered out: {}", preview.url];
+                        None
+                    }
+                    Err(e) => {
+                        log::warn!["Error during SmartFilter::check: {e}"];
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(env.concurrency.max(1))
+        .filter_map(|kept| async move { kept })
+        .collect::<Vec<_>>()
+        .await;
+
+    kept.sort_by_key(|(i, _)| *i);
+    kept.into_iter().map(|(_, preview)| preview).collect()
+}
+
+/// Embellishes the preview once its host allows it. Errors are logged, and
+/// leave the preview as it is. The content that embellishing extracts takes
+/// precedence over the content that the preview came with.
+async fn embellish_limited(
+    env: &Env,
+    limiter: &HostLimiter,
+    mut preview: Preview,
+    content: Option<ExtractedContent>,
+) -> (Preview, Option<ExtractedContent>) {
+    let _permit = match limiter.acquire(&preview.url).await {
+        Ok(permit) => permit,
+        Err(e) => {
+            log::error!("Error during HostLimiter::acquire: {e}");
+            return (preview, content);
+        }
+    };
+    let extracted = match embellish_preview(env, &mut preview).await {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            log::error!("Error during embellish_preview: {e}");
+            None
+        }
+    };
+    (preview, extracted.or(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        embellisher::{Embellisher, Embellishers},
+        utility::{
+            ai::MockLlm,
+            db::{establish_connection, get_preview, get_preview_content, test_database},
+            http::{Fetcher, FetcherOptions},
+            mock_server::counting_server,
+        },
+    };
+    use async_trait::async_trait;
+
+    /// Fetches the preview's URL and summarizes it as the response body.
+    struct Fetch;
+
+    #[async_trait(?Send)]
+    impl Embellisher for Fetch {
+        fn name(&self) -> &str {
+            "fetch"
+        }
+
+        fn matches(&self, _url: &str) -> bool {
+            true
+        }
+
+        async fn embellish(
+            &self,
+            env: &Env,
+            preview: &mut Preview,
+        ) -> Result<Option<ExtractedContent>> {
+            let text = env.fetcher.get(&preview.url).await?.text();
+            preview.summary = Some(text.clone());
+            Ok(Some(ExtractedContent::new(text, "text/plain", "fetch")))
+        }
+    }
+
+    fn test_env(database_url: &str, concurrency: usize, per_host_concurrency: usize) -> Env {
+        let mut embellishers = Embellishers::empty();
+        embellishers.register(Fetch);
+        Env {
+            fetcher: Fetcher::with_options(FetcherOptions {
+                max_retries: 0,
+                ..Default::default()
+            })
+            .unwrap(),
+            db_conn: establish_connection(database_url),
+            octocrab: octocrab::Octocrab::default(),
+            embellishers,
+            llm: Box::new(MockLlm::new("yes".to_owned())),
+            database_url: database_url.to_owned(),
+            feeds_dirpath: String::new(),
+            dry_run: false,
+            concurrency,
+            per_host_concurrency,
+        }
+    }
+
+    fn previews(urls: &[String]) -> Vec<(Preview, Option<ExtractedContent>)> {
+        urls.iter()
+            .map(|url| (Preview::from_url(url.clone()), None))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn limits_requests_per_host_and_in_total() {
+        let (port, peaks) = counting_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let env = test_env(&test_database(dir.path()), 3, 2);
+        let urls: Vec<_> = (0..6)
+            .flat_map(|i| {
+                [
+                    format!("http://127.0.0.1:{port}/a{i}?delay=50"),
+                    format!("http://localhost:{port}/b{i}?delay=50"),
+                ]
+            })
+            .collect();
+
+        let kept = process_previews(&env, &SmartFilter::default(), previews(&urls), None).await;
+
+        assert_eq!(kept.len(), urls.len());
+        let peaks = peaks.lock().unwrap();
+        assert_eq!(peaks.per_host.len(), 2);
+        for (host, peak) in &peaks.per_host {
+            assert!(*peak <= 2, "{peak} requests to {host} at once");
+        }
+        assert!(peaks.total <= 3, "{} requests at once", peaks.total);
+        assert!(peaks.total > 1, "requests weren't concurrent");
+    }
+
+    #[tokio::test]
+    async fn keeps_the_order_and_skips_failures() {
+        let (port, _) = counting_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let database_url = test_database(dir.path());
+        let env = test_env(&database_url, 4, 4);
+        // later previews respond sooner, so they finish first
+        let urls = [
+            format!("http://127.0.0.1:{port}/first?delay=60"),
+            format!("http://127.0.0.1:{port}/fail?delay=40"),
+            format!("http://127.0.0.1:{port}/second?delay=20"),
+            format!("http://127.0.0.1:{port}/third"),
+        ];
+
+        let writer = DbWriter::spawn(&database_url);
+        let kept = process_previews(
+            &env,
+            &SmartFilter::default(),
+            previews(&urls),
+            Some(&writer),
+        )
+        .await;
+        assert_eq!(writer.finish().unwrap(), 0);
+
+        let kept: Vec<_> = kept.iter().map(|preview| preview.url.as_str()).collect();
+        assert_eq!(kept, [&urls[0], &urls[2], &urls[3]]);
+
+        let mut db_conn = establish_connection(&database_url);
+        for url in [&urls[0], &urls[2], &urls[3]] {
+            let stored = get_preview(&mut db_conn, url.clone()).unwrap().unwrap();
+            assert!(stored.embellished);
+            assert!(get_preview_content(&mut db_conn, url).unwrap().is_some());
+        }
+        assert!(
+            get_preview(&mut db_conn, urls[1].clone())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     models::Preview,
-    utility::{self, indent},
+    utility::{ai::LlmBackend, indent},
 };
 use anyhow::Result;
 
@@ -13,10 +13,15 @@ pub fn into_previews(channel: rss::Channel) -> Result<Vec<Preview>> {
         .collect()
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
 pub struct SmartFilter {
     pub keywords: Vec<String>,
     pub topics: Vec<String>,
+    /// The lowest score of a preview's discussion that passes, if any.
+    pub min_score: Option<i32>,
+    /// The fewest comments in a preview's discussion that pass, if any.
+    pub min_comments: Option<i32>,
 }
 
 impl SmartFilter {
@@ -25,35 +30,49 @@ impl SmartFilter {
     }
 
     pub fn add_topics(&mut self, mut topics: Vec<String>) {
-        self.keywords.append(&mut topics);
+        self.topics.append(&mut topics);
     }
 
-    pub async fn check(&self, preview: &Preview) -> Result<bool> {
+    /// Whether the preview's discussion is popular enough. A preview without a
+    /// score or comment count doesn't pass a minimum on it.
+    pub fn check_popularity(&self, preview: &Preview) -> bool {
+        let at_least = |value: Option<i32>, min: Option<i32>| match min {
+            None => true,
+            Some(min) => value.is_some_and(|value| value >= min),
+        };
+        at_least(preview.score, self.min_score)
+            && at_least(preview.comment_count, self.min_comments)
+    }
+
+    /// Whether the preview passes the filter. Its topics are asked about `llm`.
+    pub async fn check(&self, llm: &dyn LlmBackend, preview: &Preview) -> Result<bool> {
+        if !self.check_popularity(preview) {
+            return Ok(false);
+        }
+
         let summary = match &preview.summary {
             None => return Ok(false),
             Some(summary) => summary,
         };
 
-        if !self.keywords.is_empty() {
-            let mut success = false;
-            for keyword in &self.keywords {
-                if summary.contains(keyword) {
-                    success = true;
-                    break;
-                }
-            }
-            if !success {
-                return Ok(false);
-            }
+        if !self.keywords.is_empty()
+            && !self
+                .keywords
+                .iter()
+                .any(|keyword| mentions(summary, keyword))
+        {
+            return Ok(false);
         }
 
         if !self.topics.is_empty() {
-            let response = utility::ai::gemini_cli(&format!(
-                "Consider the following passage:\n\n{}\n\nYour task is to decide if the above passage is related to any of the following topics: {}. Respond with yes or no.",
-                self.topics.join(", "),
-                indent(summary),
-            ))?;
-            if !(response.contains("yes") || response.contains("Yes")) {
+            let response = llm
+                .complete(&format!(
+                    "Consider the following passage:\n\n{}\n\nYour task is to decide if the above passage is related to any of the following topics: {}. Respond with yes or no.",
+                    indent(summary),
+                    self.topics.join(", "),
+                ))
+                .await?;
+            if !is_yes(&response) {
                 return Ok(false);
             }
         }
@@ -61,8 +80,129 @@ impl SmartFilter {
         Ok(true)
     }
 
-    pub async fn checked(&self, preview: Preview) -> Result<(bool, Preview)> {
-        let check = self.check(&preview).await?;
+    pub async fn checked(&self, llm: &dyn LlmBackend, preview: Preview) -> Result<(bool, Preview)> {
+        let check = self.check(llm, &preview).await?;
         Ok((check, preview))
     }
 }
+
+/// Whether the text mentions the keyword as a whole word or phrase, ignoring
+/// case, so that `AI` matches `ai` but not `said`.
+fn mentions(text: &str, keyword: &str) -> bool {
+    let text = text.to_lowercase();
+    let keyword = keyword.trim().to_lowercase();
+    if keyword.is_empty() {
+        return false;
+    }
+    text.match_indices(&keyword).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + keyword.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// Whether the answer to a yes-or-no question is yes, e.g. `Yes.`, but not
+/// `No, not yes` or `eyes`.
+fn is_yes(response: &str) -> bool {
+    response.trim().to_lowercase().starts_with("yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::ai::MockLlm;
+
+    fn preview(summary: &str) -> Preview {
+        let mut preview = Preview::from_url("https://example.com/".to_owned());
+        preview.summary = Some(summary.to_owned());
+        preview
+    }
+
+    #[test]
+    fn mentions_keywords_as_words_ignoring_case() {
+        let text = "Rust's new type system, as said by AI researchers";
+        assert!(mentions(text, "rust"));
+        assert!(mentions(text, "Type System"));
+        assert!(mentions(text, "ai"));
+        assert!(mentions(text, "AI"));
+        assert!(!mentions(text, "type theory"));
+        assert!(!mentions("a maintained project", "AI"));
+        assert!(!mentions("rustc", "rust"));
+        assert!(!mentions(text, " "));
+    }
+
+    #[test]
+    fn parses_yes_strictly() {
+        assert!(is_yes("yes"));
+        assert!(is_yes("  Yes, it is related.\n"));
+        assert!(is_yes("YES"));
+        assert!(!is_yes("No, it is not. Saying yes would be wrong."));
+        assert!(!is_yes("eyes"));
+        assert!(!is_yes(""));
+    }
+
+    #[tokio::test]
+    async fn filters_by_topic_with_the_llm() {
+        let filter = SmartFilter {
+            topics: vec!["programming languages".to_owned()],
+            ..Default::default()
+        };
+        let llm = MockLlm::new("No.".to_owned())
+            .rule("borrow checker", "Yes, it is.")
+            .rule("sourdough", "Not really, though eyes may differ.");
+
+        assert!(
+            filter
+                .check(&llm, &preview("A new borrow checker"))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !filter
+                .check(&llm, &preview("Baking sourdough"))
+                .await
+                .unwrap()
+        );
+        assert!(!filter.check(&llm, &preview("The weather")).await.unwrap());
+        // previews without a summary never pass
+        let empty = Preview::from_url("https://example.com/".to_owned());
+        assert!(!filter.check(&llm, &empty).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn filters_by_keyword_before_asking_the_llm() {
+        let filter = SmartFilter {
+            keywords: vec!["AI".to_owned()],
+            topics: vec!["software".to_owned()],
+            ..Default::default()
+        };
+        let llm = MockLlm::new("yes".to_owned());
+        assert!(filter.check(&llm, &preview("New ai tools")).await.unwrap());
+        assert!(!filter.check(&llm, &preview("As he said")).await.unwrap());
+
+        let llm = MockLlm::new("no".to_owned());
+        assert!(!filter.check(&llm, &preview("New AI tools")).await.unwrap());
+    }
+
+    #[test]
+    fn checks_popularity() {
+        let mut preview = Preview::from_url("https://example.com/".to_owned());
+        let filter = SmartFilter {
+            min_score: Some(100),
+            min_comments: Some(10),
+            ..Default::default()
+        };
+        assert!(SmartFilter::default().check_popularity(&preview));
+        assert!(!filter.check_popularity(&preview));
+
+        preview.score = Some(150);
+        preview.comment_count = Some(5);
+        assert!(!filter.check_popularity(&preview));
+
+        preview.comment_count = Some(10);
+        assert!(filter.check_popularity(&preview));
+
+        preview.score = Some(99);
+        assert!(!filter.check_popularity(&preview));
+    }
+}
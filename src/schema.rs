@@ -1,5 +1,67 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    arxiv_papers (arxiv_id) {
+        arxiv_id -> Text,
+        version -> Integer,
+        title -> Text,
+        authors -> Text,
+        summary -> Text,
+        published -> Nullable<Text>,
+        updated -> Nullable<Text>,
+        comment -> Nullable<Text>,
+        pdf_url -> Nullable<Text>,
+        primary_category -> Nullable<Text>,
+        categories -> Text,
+        fetched_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    feed_state (feed_name) {
+        feed_name -> Text,
+        watermark -> Text,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    github_repos (full_name) {
+        full_name -> Text,
+        owner -> Text,
+        name -> Text,
+        description -> Nullable<Text>,
+        stars -> Nullable<Integer>,
+        forks -> Nullable<Integer>,
+        language -> Nullable<Text>,
+        topics -> Text,
+        license -> Nullable<Text>,
+        pushed_at -> Nullable<Timestamp>,
+        archived -> Bool,
+        fetched_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    preview_contents (url) {
+        url -> Text,
+        content -> Text,
+        content_type -> Nullable<Text>,
+        byte_length -> BigInt,
+        extraction_method -> Text,
+        fetched_at -> Timestamp,
+        page_count -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    preview_tags (preview_url, tag_id) {
+        preview_url -> Text,
+        tag_id -> Integer,
+        provenance -> Text,
+    }
+}
+
 diesel::table! {
     previews (url) {
         url -> Text,
@@ -12,5 +74,35 @@ diesel::table! {
         published_date -> Nullable<Text>,
         tags -> Nullable<Text>,
         summary -> Nullable<Text>,
+        tags_provenance -> Nullable<Text>,
+        author -> Nullable<Text>,
+        site_name -> Nullable<Text>,
+        image_url -> Nullable<Text>,
+        published_at -> Nullable<Timestamp>,
+        score -> Nullable<Integer>,
+        comment_count -> Nullable<Integer>,
+        submitter -> Nullable<Text>,
+        discussion_url -> Nullable<Text>,
     }
 }
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::joinable!(preview_contents -> previews (url));
+diesel::joinable!(preview_tags -> previews (preview_url));
+diesel::joinable!(preview_tags -> tags (tag_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    arxiv_papers,
+    feed_state,
+    github_repos,
+    preview_contents,
+    preview_tags,
+    previews,
+    tags,
+);
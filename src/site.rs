@@ -0,0 +1,465 @@
+//! A static HTML site for browsing the published feeds.
+//!
+//! The site is written into the feeds directory, next to the feeds
+//! themselves:
+//! - `index.html` lists the feeds, tags and days,
+//! - `feeds/<feed>.html` lists the previews of a feed,
+//! - `tags/<tag>.html` lists the published previews with a tag, which are also
+//!   published as `tags/<tag>.feed.xml`,
+//! - `days/<date>.html` lists the published previews added on a day.
+use crate::{
+    Env,
+    feeds::{self, FeedConfig},
+    models::Preview,
+    utility::feed::{Contents, FeedFormat, FeedMeta},
+};
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+use url::Url;
+
+/// Renders the site for the given feeds from the previews already stored.
+pub fn render_site(env: &mut Env, feeds: &[FeedConfig]) -> Result<()> {
+    log::info!["render_site: {}", env.feeds_dirpath];
+
+    // load the previews of each feed
+    let mut feed_previews: Vec<(&FeedConfig, Vec<Preview>)> = vec![];
+    for feed in feeds {
+        let previews = feeds::get_feed_previews(env, feed)?;
+        feed_previews.push((feed, previews));
+    }
+
+    // every published preview, once
+    let mut published: BTreeMap<String, Preview> = BTreeMap::new();
+    for (_, previews) in &feed_previews {
+        for preview in previews {
+            published.insert(preview.url.clone(), preview.clone());
+        }
+    }
+    let mut published: Vec<Preview> = published.into_values().collect();
+    feeds::sort_newest_first(&mut published);
+
+    let mut tag_previews: BTreeMap<String, Vec<Preview>> = BTreeMap::new();
+    let mut day_previews: BTreeMap<NaiveDate, Vec<Preview>> = BTreeMap::new();
+    for preview in &published {
+        for tag in preview.tags().unwrap_or_default() {
+            if !tag.is_empty() {
+                tag_previews
+                    .entry(tag.to_owned())
+                    .or_default()
+                    .push(preview.clone());
+            }
+        }
+        day_previews
+            .entry(preview.added_date)
+            .or_default()
+            .push(preview.clone());
+    }
+
+    let pages = render_pages(&feed_previews, &tag_previews, &day_previews)?;
+
+    if env.dry_run {
+        for (file_path, _) in &pages {
+            log::info!["dry run: would write {file_path}"];
+        }
+        return Ok(());
+    }
+
+    write_pages(Path::new(&env.feeds_dirpath), pages)
+}
+
+/// The directories of the feeds directory that only hold pages of the site.
+const PAGE_DIRNAMES: [&str; 3] = ["feeds", "tags", "days"];
+
+/// Writes the pages into the feeds directory, and removes the pages that
+/// earlier renders wrote but this one didn't, like those of tags that have
+/// since been renamed, merged or emptied.
+fn write_pages(dirpath: &Path, pages: Vec<(String, String)>) -> Result<()> {
+    for dirname in PAGE_DIRNAMES {
+        fs::create_dir_all(dirpath.join(dirname))?;
+    }
+    let mut written = BTreeSet::new();
+    for (file_path, content) in pages {
+        let file_path = dirpath.join(file_path);
+        fs::write(&file_path, content)?;
+        written.insert(file_path);
+    }
+
+    for dirname in PAGE_DIRNAMES {
+        for entry in fs::read_dir(dirpath.join(dirname))? {
+            let path = entry?.path();
+            if written.contains(&path) {
+                continue;
+            }
+            log::info!["removing stale page: {}", path.display()];
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders every page of the site, as pairs of a file path, relative to the
+/// feeds directory, and its content.
+fn render_pages(
+    feed_previews: &[(&FeedConfig, Vec<Preview>)],
+    tag_previews: &BTreeMap<String, Vec<Preview>>,
+    day_previews: &BTreeMap<NaiveDate, Vec<Preview>>,
+) -> Result<Vec<(String, String)>> {
+    let mut pages = vec![];
+    let slugs = TagSlugs::new(tag_previews.keys());
+
+    // index
+    {
+        let mut body = String::new();
+        body.push_str("<h2>Feeds</h2>\n<ul>\n");
+        for (feed, previews) in feed_previews {
+            let meta = feed.meta();
+            body.push_str(&format!(
+                "<li><a href=\"feeds/{}.html\">{}</a> ({}) &middot; {}<br>{}</li>\n",
+                escape(&meta.stem),
+                escape(&feed.title),
+                previews.len(),
+                format_links(&feed_format_links(feed, "")),
+                escape(&feed.description),
+            ));
+        }
+        body.push_str("</ul>\n<h2>Tags</h2>\n<p class=\"tags\">\n");
+        for (tag, previews) in tag_previews {
+            body.push_str(&format!(
+                "<a href=\"tags/{}.html\">{}</a> ({})\n",
+                slugs.get(tag),
+                escape(tag),
+                previews.len()
+            ));
+        }
+        body.push_str("</p>\n<h2>Days</h2>\n<ul>\n");
+        for (day, previews) in day_previews.iter().rev() {
+            body.push_str(&format!(
+                "<li><a href=\"days/{day}.html\">{day}</a> ({})</li>\n",
+                previews.len()
+            ));
+        }
+        body.push_str("</ul>\n");
+        pages.push((
+            "index.html".to_owned(),
+            render_page("linkstitcher", "", &body),
+        ));
+    }
+
+    // feeds
+    for (feed, previews) in feed_previews {
+        let meta = feed.meta();
+        let body = format!(
+            "<p>{}</p>\n<p>{}</p>\n{}",
+            escape(&feed.description),
+            format_links(&feed_format_links(feed, "../")),
+            render_previews(previews, "../", &slugs),
+        );
+        pages.push((
+            format!("feeds/{}.html", meta.stem),
+            render_page(&feed.title, "../", &body),
+        ));
+    }
+
+    // tags
+    for (tag, previews) in tag_previews {
+        let slug = slugs.get(tag);
+        let meta = FeedMeta {
+            title: format!("linkstitcher/tags/{tag}"),
+            description: format!("The linkstitcher previews tagged {tag}"),
+            stem: format!("tags/{slug}"),
+        };
+        let body = format!(
+            "<p><a href=\"{slug}.feed.xml\">RSS</a></p>\n{}",
+            render_previews(previews, "../", &slugs)
+        );
+        pages.push((
+            format!("tags/{slug}.html"),
+            render_page(&format!("Tagged {tag}"), "../", &body),
+        ));
+        pages.push((
+            FeedFormat::Rss.filename(&meta.stem),
+            // RSS items only carry the summary, so contents aren't needed
+            FeedFormat::Rss.render(&meta, previews, &Contents::new())?,
+        ));
+    }
+
+    // days
+    for (day, previews) in day_previews {
+        pages.push((
+            format!("days/{day}.html"),
+            render_page(
+                &format!("Added on {day}"),
+                "../",
+                &render_previews(previews, "../", &slugs),
+            ),
+        ));
+    }
+
+    Ok(pages)
+}
+
+fn render_page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<style>
+body {{ max-width: 48rem; margin: 2rem auto; padding: 0 1rem; font-family: sans-serif; line-height: 1.5; }}
+.preview {{ margin-bottom: 1.5rem; }}
+.meta, .summary {{ color: #555; font-size: 0.9rem; }}
+.summary {{ white-space: pre-line; }}
+</style>
+</head>
+<body>
+<nav><a href=\"{root}index.html\">linkstitcher</a></nav>
+<h1>{title}</h1>
+{body}</body>
+</html>
+",
+        title = escape(title),
+    )
+}
+
+fn render_previews(previews: &[Preview], root: &str, slugs: &TagSlugs) -> String {
+    let mut html = String::new();
+    for preview in previews {
+        let title = preview.title.as_deref().unwrap_or(&preview.url);
+        html.push_str("<div class=\"preview\">\n");
+        html.push_str(&format!("{}\n", link(&preview.url, &escape(title))));
+        let mut meta = vec![format!(
+            "<a href=\"{root}days/{day}.html\">{day}</a>",
+            day = preview.added_date
+        )];
+        if let Some(source) = &preview.source {
+            meta.push(escape(source));
+        }
+        if let Some(discussion_url) = &preview.discussion_url {
+            let mut counts = vec![];
+            if let Some(score) = preview.score {
+                counts.push(format!("{score} points"));
+            }
+            if let Some(comment_count) = preview.comment_count {
+                counts.push(format!("{comment_count} comments"));
+            }
+            if counts.is_empty() {
+                counts.push("discussion".to_owned());
+            }
+            meta.push(link(discussion_url, &counts.join(", ")));
+        }
+        for tag in preview.tags().unwrap_or_default() {
+            if !tag.is_empty() {
+                meta.push(format!(
+                    "<a href=\"{root}tags/{}.html\">#{}</a>",
+                    slugs.get(tag),
+                    escape(tag)
+                ));
+            }
+        }
+        html.push_str(&format!(
+            "<div class=\"meta\">{}</div>\n",
+            meta.join(" &middot; ")
+        ));
+        if let Some(summary) = &preview.summary {
+            html.push_str(&format!(
+                "<div class=\"summary\">{}</div>\n",
+                escape(summary)
+            ));
+        }
+        html.push_str("</div>\n");
+    }
+    html
+}
+
+/// Links to each format of the feed, relative to `root`.
+fn feed_format_links(feed: &FeedConfig, root: &str) -> Vec<(String, &'static str)> {
+    let meta = feed.meta();
+    feed.formats
+        .iter()
+        .map(|format| {
+            let label = match format {
+                FeedFormat::Rss => "RSS",
+                FeedFormat::Atom => "Atom",
+                FeedFormat::JsonFeed => "JSON Feed",
+            };
+            (format!("{root}{}", format.filename(&meta.stem)), label)
+        })
+        .collect()
+}
+
+fn format_links(links: &[(String, &str)]) -> String {
+    links
+        .iter()
+        .map(|(href, label)| format!("<a href=\"{}\">{label}</a>", escape(href)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A link to an external URL, or only its label if the URL isn't http(s), since
+/// the URLs of previews come from upstream feeds and could be `javascript:`.
+fn link(url: &str, label: &str) -> String {
+    let is_http = Url::parse(url)
+        .ok()
+        .is_some_and(|url| matches!(url.scheme(), "http" | "https"));
+    if is_http {
+        format!("<a href=\"{}\">{label}</a>", escape(url))
+    } else {
+        label.to_owned()
+    }
+}
+
+/// The file name of each tag. Tags whose [`slug`]s are the same, like `c++`
+/// and `c`, are told apart by a number, in the order of their names.
+struct TagSlugs {
+    slugs: BTreeMap<String, String>,
+}
+
+impl TagSlugs {
+    fn new<'a>(tags: impl Iterator<Item = &'a String>) -> Self {
+        let tags: BTreeSet<&String> = tags.collect();
+        let mut used: BTreeSet<String> = BTreeSet::new();
+        let mut slugs = BTreeMap::new();
+        for tag in tags {
+            let base = slug(tag);
+            let mut slug = base.clone();
+            let mut n = 2;
+            while !used.insert(slug.clone()) {
+                slug = format!("{base}-{n}");
+                n += 1;
+            }
+            slugs.insert(tag.to_owned(), slug);
+        }
+        TagSlugs { slugs }
+    }
+
+    fn get(&self, tag: &str) -> String {
+        self.slugs.get(tag).cloned().unwrap_or_else(|| slug(tag))
+    }
+}
+
+/// A file name for the tag, e.g. `type-theory` for `type theory` or
+/// `c-plus-plus` for `c++`. Different tags may have the same slug, see
+/// [`TagSlugs`].
+fn slug(tag: &str) -> String {
+    let slug = tag
+        .chars()
+        .map(|c| match c {
+            '+' => "-plus-".to_owned(),
+            '#' => "-sharp-".to_owned(),
+            c if c.is_alphanumeric() => c.to_lowercase().to_string(),
+            _ => "-".to_owned(),
+        })
+        .collect::<String>();
+    let slug = slug
+        .split("-")
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "tag".to_owned()
+    } else {
+        slug
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_colliding_tag_slugs_apart() {
+        let tags: Vec<String> = [
+            "c",
+            "c++",
+            "c#",
+            "type theory",
+            "type-theory",
+            "c-plus-plus",
+        ]
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect();
+        let slugs = TagSlugs::new(tags.iter());
+        assert_eq!(slugs.get("c"), "c");
+        assert_eq!(slugs.get("c#"), "c-sharp");
+        assert_eq!(slugs.get("c++"), "c-plus-plus");
+        assert_eq!(slugs.get("c-plus-plus"), "c-plus-plus-2");
+        assert_eq!(slugs.get("type theory"), "type-theory");
+        assert_eq!(slugs.get("type-theory"), "type-theory-2");
+        assert_eq!(slug("  ???  "), "tag");
+    }
+
+    #[test]
+    fn only_links_http_urls() {
+        let mut safe = Preview::from_url("https://example.com/a?b=1&c=2".to_owned());
+        safe.title = Some("<Safe>".to_owned());
+        let mut unsafe_ = Preview::from_url("javascript:alert(1)".to_owned());
+        unsafe_.title = Some("Unsafe".to_owned());
+        unsafe_.discussion_url = Some("javascript:alert(2)".to_owned());
+
+        let html = render_previews(&[safe, unsafe_], "", &TagSlugs::new([].iter()));
+        assert!(html.contains("<a href=\"https://example.com/a?b=1&amp;c=2\">&lt;Safe&gt;</a>"));
+        assert!(html.contains("Unsafe"));
+        assert!(!html.contains("javascript:"));
+    }
+
+    #[test]
+    fn removes_stale_pages() {
+        let dirpath =
+            std::env::temp_dir().join(format!("linkstitcher-site-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dirpath);
+        fs::create_dir_all(&dirpath).unwrap();
+        fs::write(dirpath.join("hackernews.feed.xml"), "feed").unwrap();
+        let page = |file_path: &str| (file_path.to_owned(), file_path.to_owned());
+
+        write_pages(
+            &dirpath,
+            vec![
+                page("index.html"),
+                page("tags/old.html"),
+                page("tags/old.feed.xml"),
+                page("days/2026-10-16.html"),
+            ],
+        )
+        .unwrap();
+        fs::create_dir_all(dirpath.join("tags/stale")).unwrap();
+        write_pages(&dirpath, vec![page("index.html"), page("tags/new.html")]).unwrap();
+
+        let mut files = vec![];
+        for dirname in ["", "feeds", "tags", "days"] {
+            for entry in fs::read_dir(dirpath.join(dirname)).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_file() {
+                    files.push(path.strip_prefix(&dirpath).unwrap().display().to_string());
+                }
+            }
+        }
+        files.sort();
+        assert_eq!(
+            files,
+            ["hackernews.feed.xml", "index.html", "tags/new.html"]
+        );
+        assert!(!dirpath.join("tags/stale").exists());
+        fs::remove_dir_all(&dirpath).unwrap();
+    }
+}
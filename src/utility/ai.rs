@@ -1,28 +1,288 @@
+//! Backends for the LLM used to tag and filter previews.
+//!
+//! The backend is chosen by `LLM_BACKEND` (see [`backend_from_config`]):
+//! - `gemini` (the default) runs the `gemini` CLI,
+//! - `openai` uses an OpenAI-compatible HTTP endpoint, such as a local
+//!   llama.cpp or Ollama server, at `LLM_BASE_URL` with `LLM_MODEL` and the
+//!   optional `LLM_API_KEY`,
+//! - `mock` always responds with `LLM_MOCK_RESPONSE`.
+//!
+//! In offline mode, only the `mock` backend responds.
+use crate::{
+    config,
+    utility::http::{Fetcher, FetcherOptions},
+};
 use anyhow::{Result, anyhow};
+use async_trait::async_trait;
 use thiserror::Error;
 
+#[async_trait(?Send)]
+pub trait LlmBackend {
+    /// A short, human-readable name, used for logging.
+    fn name(&self) -> &str;
+
+    /// Responds to a single prompt.
+    async fn complete(&self, prompt: &str) -> Result<String>;
+}
+
+pub const DEFAULT_LLM_BASE_URL: &str = "http://localhost:11434/v1";
+pub const DEFAULT_LLM_MODEL: &str = "llama3.2";
+/// How long to wait for a completion, which is only sent once it's done.
+pub const LLM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// Builds the backend configured by the `LLM_*` environment variables.
+pub fn backend_from_config(offline: bool) -> Result<Box<dyn LlmBackend>> {
+    backend_named(config::LLM_BACKEND.as_deref().unwrap_or("gemini"), offline)
+}
+
+/// Builds the backend with the given name, configured by the other `LLM_*`
+/// environment variables. In offline mode, any backend but `mock` refuses to
+/// respond.
+pub fn backend_named(name: &str, offline: bool) -> Result<Box<dyn LlmBackend>> {
+    if offline && name != "mock" {
+        return Ok(Box::new(Offline {
+            backend: name.to_owned(),
+        }));
+    }
+    match name {
+        "gemini" => Ok(Box::new(GeminiCli::default())),
+        "openai" => Ok(Box::new(OpenAiCompatible {
+            fetcher: Fetcher::with_options(FetcherOptions {
+                read_timeout: LLM_TIMEOUT,
+                timeout: LLM_TIMEOUT,
+                ..Default::default()
+            })?,
+            base_url: config::LLM_BASE_URL
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LLM_BASE_URL.to_owned()),
+            model: config::LLM_MODEL
+                .clone()
+                .unwrap_or_else(|| DEFAULT_LLM_MODEL.to_owned()),
+            api_key: config::LLM_API_KEY.clone(),
+        })),
+        "mock" => Ok(Box::new(MockLlm::new(
+            config::LLM_MOCK_RESPONSE.clone().unwrap_or_default(),
+        ))),
+        backend => Err(anyhow!("unknown LLM backend: {backend}")),
+    }
+}
+
 #[derive(Debug, Error)]
-#[error("Gemini CLI error")]
+#[error("Gemini CLI error ({status}): {stderr}")]
 struct GeminiCliError {
+    status: std::process::ExitStatus,
     stderr: String,
 }
 
-pub fn gemini_cli(prompt: &str) -> Result<String> {
-    log::trace!["gemini_cli: {prompt}"];
-    let escaped_prompt = prompt
-        .chars()
-        .flat_map(|c| c.escape_default())
-        .collect::<String>();
-    let output = std::process::Command::new("sh")
-        .arg("-c")
-        .arg(format!("gemini -p \"{escaped_prompt}\""))
-        .output()?;
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+/// Runs the `gemini` CLI with the prompt as a single argument, so that the
+/// prompt is never interpreted by a shell.
+#[derive(Debug, Clone)]
+pub struct GeminiCli {
+    pub program: String,
+}
+
+impl Default for GeminiCli {
+    fn default() -> Self {
+        GeminiCli {
+            program: "gemini".to_owned(),
+        }
+    }
+}
 
-    if stderr.contains("Error") || stderr.contains("error") {
-        return Err(anyhow!(GeminiCliError { stderr }));
+#[async_trait(?Send)]
+impl LlmBackend for GeminiCli {
+    fn name(&self) -> &str {
+        "gemini"
     }
 
-    Ok(stdout)
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        log::trace!["gemini_cli: {prompt}"];
+        let output = tokio::process::Command::new(&self.program)
+            .arg("-p")
+            .arg(prompt)
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(anyhow!(GeminiCliError {
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            }));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+/// Uses the chat completions endpoint of an OpenAI-compatible API.
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatible {
+    pub fetcher: Fetcher,
+    /// The base URL of the API, e.g. `http://localhost:8080/v1`.
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ChatCompletion {
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatCompletionMessage,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ChatCompletionMessage {
+    content: Option<String>,
+}
+
+#[async_trait(?Send)]
+impl LlmBackend for OpenAiCompatible {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        log::trace!["openai_compatible: {prompt}"];
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches("/"));
+        let mut request = self.fetcher.client().post(url).json(&serde_json::json!({
+            "model": self.model,
+            "messages": [{ "role": "user", "content": prompt }],
+            "temperature": 0,
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let completion = self.fetcher.send(request).await?.json::<ChatCompletion>()?;
+        completion
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or(anyhow!("the chat completion has no content"))
+    }
+}
+
+/// Stands in for a backend that would need the network, in offline mode.
+#[derive(Debug, Clone)]
+pub struct Offline {
+    pub backend: String,
+}
+
+#[async_trait(?Send)]
+impl LlmBackend for Offline {
+    fn name(&self) -> &str {
+        &self.backend
+    }
+
+    async fn complete(&self, _prompt: &str) -> Result<String> {
+        Err(anyhow!(
+            "the {} LLM backend can't be used offline",
+            self.backend
+        ))
+    }
+}
+
+/// Responds deterministically, for tests and dry runs: with the response of
+/// the first rule whose pattern the prompt contains, or else with the default
+/// response.
+#[derive(Debug, Clone, Default)]
+pub struct MockLlm {
+    pub rules: Vec<(String, String)>,
+    pub default_response: String,
+}
+
+impl MockLlm {
+    pub fn new(default_response: String) -> Self {
+        MockLlm {
+            rules: vec![],
+            default_response,
+        }
+    }
+
+    pub fn rule(mut self, pattern: &str, response: &str) -> Self {
+        self.rules.push((pattern.to_owned(), response.to_owned()));
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl LlmBackend for MockLlm {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self
+            .rules
+            .iter()
+            .find(|(pattern, _)| prompt.contains(pattern))
+            .map(|(_, response)| response.clone())
+            .unwrap_or_else(|| self.default_response.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::mock_server::mock_server;
+
+    #[test]
+    fn builds_backends_by_name() {
+        for name in ["gemini", "openai", "mock"] {
+            assert_eq!(backend_named(name, false).unwrap().name(), name);
+        }
+        assert!(backend_named("gpt", false).is_err());
+    }
+
+    #[tokio::test]
+    async fn only_mocks_respond_offline() {
+        for name in ["gemini", "openai"] {
+            let llm = backend_named(name, true).unwrap();
+            assert_eq!(llm.name(), name);
+            let e = llm.complete("Is it?").await.unwrap_err();
+            assert_eq!(
+                e.to_string(),
+                format!("the {name} LLM backend can't be used offline")
+            );
+        }
+        let llm = backend_named("mock", true).unwrap();
+        assert_eq!(llm.name(), "mock");
+        assert!(llm.complete("Is it?").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn completes_with_an_openai_compatible_api() {
+        let base_url = mock_server(vec![(
+            "/v1/chat/completions",
+            r#"{"choices": [{"message": {"role": "assistant", "content": "yes"}}]}"#,
+        )])
+        .await;
+        let llm = OpenAiCompatible {
+            fetcher: Fetcher::new().unwrap(),
+            base_url: format!("{base_url}/v1/"),
+            model: "test".to_owned(),
+            api_key: Some("key".to_owned()),
+        };
+        assert_eq!(llm.complete("Is it?").await.unwrap(), "yes");
+
+        let llm = OpenAiCompatible {
+            base_url: format!("{base_url}/v2"),
+            ..llm
+        };
+        assert!(llm.complete("Is it?").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn mocks_responses_by_rule() {
+        let llm = MockLlm::new("no".to_owned())
+            .rule("rust", "yes")
+            .rule("rust or haskell", "never reached");
+        assert_eq!(llm.complete("about rust or haskell").await.unwrap(), "yes");
+        assert_eq!(llm.complete("about cooking").await.unwrap(), "no");
+    }
 }
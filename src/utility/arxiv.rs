@@ -4,17 +4,19 @@
 //! This module was adapted from from arxiv-rs by Jun Hirako.
 //!   - repository: https://github.com/moisutsu/arxiv-rs
 //!   - author: https://github.com/moisutsu
+use crate::{config, utility::http::Fetcher};
 use anyhow::{Result, anyhow};
 use map_macro::hash_map;
 use std::collections::HashMap;
 use std::fs;
 use std::io::Write;
+use thiserror::Error;
 use xml::EventReader;
 use xml::reader::XmlEvent;
 
-pub async fn fetch_by_url(arxiv_url: &str) -> Result<Arxiv> {
+pub async fn fetch_by_url(fetcher: &Fetcher, arxiv_url: &str) -> Result<Arxiv> {
     let arxiv_id = get_id_from_url(arxiv_url).ok_or_else(|| anyhow!("Invalid ArXiv URL"))?;
-    fetch_by_id(arxiv_id).await
+    fetch_by_id(fetcher, arxiv_id).await
 }
 
 pub fn get_id_from_url(url: &str) -> Option<&str> {
@@ -29,9 +31,25 @@ pub fn get_id_from_url(url: &str) -> Option<&str> {
     }
 }
 
-pub async fn fetch_by_id(arxiv_id: &str) -> Result<Arxiv> {
+/// Splits the version off of an id or the URL of an abstract page, e.g. into
+/// `2410.12345` and `2` for `http://arxiv.org/abs/2410.12345v2`.
+pub fn split_version(id: &str) -> (&str, Option<i32>) {
+    let id = id.rsplit_once("/abs/").map(|(_, id)| id).unwrap_or(id);
+    match id.rsplit_once("v") {
+        Some((id_without_version, version))
+            if !id_without_version.is_empty()
+                && !version.is_empty()
+                && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            (id_without_version, version.parse().ok())
+        }
+        _ => (id, None),
+    }
+}
+
+pub async fn fetch_by_id(fetcher: &Fetcher, arxiv_id: &str) -> Result<Arxiv> {
     let query = ArxivQueryBuilder::new().id_list(arxiv_id).build();
-    let result = fetch_arxivs(query).await?;
+    let result = fetch_arxivs(fetcher, query).await?;
     if let Some(result) = result.first() {
         Ok(result.clone())
     } else {
@@ -55,6 +73,10 @@ pub struct Arxiv {
     pub pdf_url: String,
     pub html_url: String,
     pub comment: Option<String>,
+    /// The affiliations of each of the authors, in the same order.
+    pub affiliations: Vec<Vec<String>>,
+    pub doi: Option<String>,
+    pub journal_ref: Option<String>,
 }
 
 impl Arxiv {
@@ -63,8 +85,8 @@ impl Arxiv {
     }
 
     /// Save the paper as a pdf from the information stored by the structure.
-    pub async fn fetch_pdf(&self, out_path: &str) -> Result<()> {
-        let body = reqwest::get(&self.pdf_url).await?.bytes().await?;
+    pub async fn fetch_pdf(&self, fetcher: &Fetcher, out_path: &str) -> Result<()> {
+        let body = fetcher.get(&self.pdf_url).await?.body;
         let out_path = if out_path.ends_with(".pdf") {
             out_path.to_string()
         } else {
@@ -126,10 +148,17 @@ pub struct ArxivQueryBuilder {
     pub sort_order: String,
 }
 
+/// The default base URL of queries, which the `ARXIV_API_URL` environment
+/// variable overrides.
+pub const DEFAULT_API_URL: &str = "http://export.arxiv.org/api/query?";
+
 impl ArxivQueryBuilder {
     pub fn new() -> Self {
         ArxivQueryBuilder {
-            base_url: "http://export.arxiv.org/api/query?".to_string(),
+            base_url: config::ARXIV_API_URL
+                .as_deref()
+                .unwrap_or(DEFAULT_API_URL)
+                .to_string(),
             ..ArxivQueryBuilder::default()
         }
     }
@@ -191,128 +220,253 @@ impl ArxivQueryBuilder {
 
 /// Fetch the paper information using the arXiv API.
 /// # Example
-/// ```rust
+/// ```ignore
 /// use arxiv::{fetch_arxivs, query};
 ///
 /// let query = query!(search_query = "cat:cs.CL");
 /// // arxivs type is Vec<Arxiv>
 /// let arxivs = fetch_arxivs(query).await?;
 /// ```
-pub async fn fetch_arxivs(query: ArxivQuery) -> Result<Vec<Arxiv>> {
-    let body = reqwest::get(query.to_url()).await?.text().await?;
-    let arxivs = parse_data(body)?;
-    Ok(arxivs)
+pub async fn fetch_arxivs(fetcher: &Fetcher, query: ArxivQuery) -> Result<Vec<Arxiv>> {
+    Ok(fetch_arxiv_feed(fetcher, query).await?.entries)
+}
+
+/// Fetch a page of results using the arXiv API, along with how many results
+/// there are in all.
+pub async fn fetch_arxiv_feed(fetcher: &Fetcher, query: ArxivQuery) -> Result<ArxivFeed> {
+    let body = fetcher.get(&query.to_url()).await?.text();
+    Ok(parse_feed(&body)?)
+}
+
+/// How far before the newest paper that the last run saw the next run looks
+/// again. A paper is `published` when it is submitted, but only announced up to
+/// a few days later, e.g. after a weekend, so papers that were submitted just
+/// before the newest one may not have been there to see yet.
+pub const WATERMARK_OVERLAP: chrono::TimeDelta = chrono::TimeDelta::days(3);
+
+/// The papers that [`fetch_new_articles`] found.
+#[derive(Debug, Clone, Default)]
+pub struct NewArticles {
+    /// The papers, newest first. Some may have been seen before, since the
+    /// runs overlap by [`WATERMARK_OVERLAP`].
+    pub articles: Vec<Arxiv>,
+    /// When the newest paper that has now been seen was published. It is only
+    /// later than that of the last run if the paging reached the papers that
+    /// the last run saw, so that none are skipped.
+    pub last_published: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Pages through the papers that match the query, newest first, back to
+/// [`WATERMARK_OVERLAP`] before `last_published`, the newest paper that the
+/// last run saw, but through at most `max_results` papers. Without a last run,
+/// the newest `max_results` papers are fetched.
+pub async fn fetch_new_articles(
+    fetcher: &Fetcher,
+    query: &ArxivQueryBuilder,
+    page_size: usize,
+    max_results: usize,
+    last_published: Option<chrono::DateTime<chrono::Utc>>,
+    page_delay: std::time::Duration,
+) -> Result<NewArticles> {
+    let cutoff = last_published.map(|time| time - WATERMARK_OVERLAP);
+    let mut articles = vec![];
+    let mut newest_published = None;
+    // whether the paging reached the papers that the last run saw
+    let mut reached = last_published.is_none();
+
+    let mut start = 0;
+    'pages: while start < max_results {
+        if start > 0 {
+            tokio::time::sleep(page_delay).await;
+        }
+        let query = query
+            .sort_by("submittedDate")
+            .sort_order("descending")
+            .start(start as i32)
+            .max_results(page_size.min(max_results - start) as i32)
+            .build();
+        let page = fetch_arxiv_feed(fetcher, query).await?;
+        if page.entries.is_empty() {
+            reached = true;
+            break;
+        }
+        start += page.entries.len();
+
+        for article in page.entries {
+            let published = crate::utility::date::parse_date(&article.published);
+            if let (Some(published), Some(cutoff)) = (published, cutoff)
+                && published < cutoff
+            {
+                reached = true;
+                break 'pages;
+            }
+            newest_published = newest_published.max(published);
+            articles.push(article);
+        }
+        if page.total_results.is_some_and(|total| start >= total) {
+            reached = true;
+            break;
+        }
+    }
+
+    if !reached {
+        log::warn![
+            "arxiv: the papers that the last run saw are more than {max_results} papers back, \
+             so the papers between them aren't fetched"
+        ];
+    }
+    Ok(NewArticles {
+        articles,
+        last_published: match reached {
+            true => last_published.max(newest_published),
+            false => last_published,
+        },
+    })
+}
+
+/// A page of results of a query, with the
+/// [OpenSearch](https://info.arxiv.org/help/api/user-manual.html#_opensearch_extension_elements)
+/// totals that say where the page is among all of the results.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct ArxivFeed {
+    pub total_results: Option<usize>,
+    pub start_index: Option<usize>,
+    pub items_per_page: Option<usize>,
+    pub entries: Vec<Arxiv>,
+}
+
+#[derive(Debug, Error)]
+pub enum ArxivError {
+    #[error("malformed arXiv response: {0}")]
+    Xml(#[from] xml::reader::Error),
+    /// The API responds to a bad query with a feed of a single entry that
+    /// describes the error.
+    #[error("arXiv API error: {message}")]
+    Api { message: String },
+    #[error("truncated arXiv response")]
+    Truncated,
 }
 
-fn parse_data(body: String) -> Result<Vec<Arxiv>> {
-    let mut parser = EventReader::from_str(&body);
-    let mut arxiv = Arxiv::new();
-    let mut arxivs = Vec::new();
+/// The id of the entries that the API responds with to a bad query, e.g.
+/// `http://arxiv.org/api/errors#incorrect_id_format_for_1234.1234v1`.
+const API_ERROR_ID: &str = "arxiv.org/api/errors";
+
+/// Parses a response of the arXiv API, which is an Atom feed with arXiv's and
+/// OpenSearch's extensions.
+///
+/// The text of an element may arrive in several events, e.g. around entities
+/// and CDATA sections, so it is accumulated until the element ends.
+pub fn parse_feed(body: &str) -> Result<ArxivFeed, ArxivError> {
+    let mut feed = ArxivFeed::default();
+    let mut entry: Option<Arxiv> = None;
+    let mut author: Option<(String, Vec<String>)> = None;
+    let mut text = String::new();
+    let mut finished = false;
 
-    'outer: loop {
-        match parser.next()? {
+    for event in EventReader::from_str(body) {
+        match event? {
             XmlEvent::StartElement {
                 name, attributes, ..
-            } => match &name.local_name[..] {
-                "entry" => {
-                    arxiv = Arxiv::new();
-                }
-                "id" => {
-                    if let XmlEvent::Characters(id) = parser.next()? {
-                        arxiv.id = id;
-                    }
-                }
-                "updated" => {
-                    if let XmlEvent::Characters(updated) = parser.next()? {
-                        arxiv.updated = updated
-                    }
-                }
-                "published" => {
-                    if let XmlEvent::Characters(published) = parser.next()? {
-                        arxiv.published = published
-                    }
-                }
-                "title" => {
-                    if let XmlEvent::Characters(title) = parser.next()? {
-                        arxiv.title = title
-                    }
-                }
-                "summary" => {
-                    if let XmlEvent::Characters(summary) = parser.next()? {
-                        arxiv.summary = summary
-                    }
-                }
-                "author" => {
-                    parser.next()?;
-                    parser.next()?;
-                    if let XmlEvent::Characters(author) = parser.next()? {
-                        arxiv.authors.push(author);
-                    }
-                }
-                "primary_category" => {
-                    if let Some(attribute) = attributes
+            } => {
+                text.clear();
+                let attribute = |local_name: &str| {
+                    attributes
                         .iter()
-                        .find(|attr| attr.name.local_name == "term")
-                    {
-                        arxiv.primary_category = attribute.value.clone();
-                        arxiv.primary_category_name = get_category_code_name(&attribute.value);
+                        .find(|attr| attr.name.local_name == local_name)
+                        .map(|attr| attr.value.clone())
+                };
+                match (&name.local_name[..], entry.as_mut()) {
+                    ("entry", _) => entry = Some(Arxiv::new()),
+                    ("author", Some(_)) => author = Some((String::new(), vec![])),
+                    ("primary_category", Some(arxiv)) => {
+                        if let Some(term) = attribute("term") {
+                            arxiv.primary_category_name = get_category_code_name(&term);
+                            arxiv.primary_category = term;
+                        }
                     }
-                }
-                "category" => {
-                    if let Some(attribute) = attributes
-                        .iter()
-                        .find(|attr| attr.name.local_name == "term")
-                    {
-                        arxiv.categories.push(attribute.value.clone());
-                        arxiv
-                            .category_names
-                            .push(get_category_code_name(&attribute.value));
+                    ("category", Some(arxiv)) => {
+                        if let Some(term) = attribute("term") {
+                            arxiv.category_names.push(get_category_code_name(&term));
+                            arxiv.categories.push(term);
+                        }
+                    }
+                    ("link", Some(arxiv)) => {
+                        let Some(href) = attribute("href") else {
+                            continue;
+                        };
+                        if attribute("title").as_deref() == Some("pdf") {
+                            arxiv.pdf_url = format!("{}.pdf", https(&href));
+                        }
+                        if attribute("type").as_deref() == Some("text/html") {
+                            arxiv.html_url = https(&href);
+                        }
                     }
+                    _ => (),
                 }
-                "link" => {
-                    if attributes
-                        .iter()
-                        .any(|attr| attr.name.local_name == "title" && attr.value == "pdf")
-                        && let Some(attribute) = attributes
-                            .iter()
-                            .find(|attr| attr.name.local_name == "href")
-                    {
-                        arxiv.pdf_url = format!(
-                            "{}.pdf",
-                            attribute.value.replacen("http", "https", 1).clone()
-                        );
+            }
+            XmlEvent::Characters(s) | XmlEvent::CData(s) | XmlEvent::Whitespace(s) => {
+                text.push_str(&s);
+            }
+            XmlEvent::EndElement { name } => {
+                let text = std::mem::take(&mut text);
+                match (&name.local_name[..], entry.as_mut(), author.as_mut()) {
+                    ("name", Some(_), Some((author_name, _))) => *author_name = collapse(&text),
+                    ("affiliation", Some(_), Some((_, affiliations))) => {
+                        affiliations.push(collapse(&text))
                     }
-
-                    if attributes
-                        .iter()
-                        .any(|attr| attr.name.local_name == "type" && attr.value == "text/html")
-                        && let Some(attribute) = attributes
-                            .iter()
-                            .find(|attr| attr.name.local_name == "href")
-                    {
-                        arxiv.html_url = attribute.value.replacen("http", "https", 1).clone();
+                    ("author", Some(arxiv), _) => {
+                        if let Some((author_name, affiliations)) = author.take() {
+                            arxiv.authors.push(author_name);
+                            arxiv.affiliations.push(affiliations);
+                        }
                     }
-                }
-                "comment" => {
-                    if let XmlEvent::Characters(comment) = parser.next()? {
-                        arxiv.comment = Some(comment);
+                    ("id", Some(arxiv), _) => arxiv.id = text.trim().to_owned(),
+                    ("updated", Some(arxiv), _) => arxiv.updated = text.trim().to_owned(),
+                    ("published", Some(arxiv), _) => arxiv.published = text.trim().to_owned(),
+                    ("title", Some(arxiv), _) => arxiv.title = collapse(&text),
+                    ("summary", Some(arxiv), _) => arxiv.summary = text.trim().to_owned(),
+                    ("comment", Some(arxiv), _) => arxiv.comment = Some(text.trim().to_owned()),
+                    ("doi", Some(arxiv), _) => arxiv.doi = Some(text.trim().to_owned()),
+                    ("journal_ref", Some(arxiv), _) => arxiv.journal_ref = Some(collapse(&text)),
+                    ("entry", Some(_), _) => {
+                        let arxiv = entry.take().unwrap_or_default();
+                        if arxiv.id.contains(API_ERROR_ID) {
+                            return Err(ArxivError::Api {
+                                message: arxiv.summary,
+                            });
+                        }
+                        feed.entries.push(arxiv);
                     }
+                    ("totalResults", None, _) => feed.total_results = text.trim().parse().ok(),
+                    ("startIndex", None, _) => feed.start_index = text.trim().parse().ok(),
+                    ("itemsPerPage", None, _) => feed.items_per_page = text.trim().parse().ok(),
+                    ("feed", None, _) => finished = true,
+                    _ => (),
                 }
-                _ => (),
-            },
-            XmlEvent::EndElement { name } => match &name.local_name[..] {
-                "entry" => {
-                    arxivs.push(arxiv.clone());
-                }
-                "feed" => {
-                    break 'outer;
-                }
-                _ => (),
-            },
+            }
             _ => (),
         }
     }
-    Ok(arxivs)
+
+    if finished {
+        Ok(feed)
+    } else {
+        Err(ArxivError::Truncated)
+    }
+}
+
+/// Collapses the runs of whitespace in the text, like the line breaks that
+/// arXiv wraps long titles and names with.
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Upgrades an `http://` link to `https://`. Other links are kept as they are.
+fn https(href: &str) -> String {
+    match href.strip_prefix("http://") {
+        Some(rest) => format!("https://{rest}"),
+        None => href.to_owned(),
+    }
 }
 
 fn get_category_code_name(code: &str) -> String {
@@ -481,3 +635,193 @@ lazy_static::lazy_static! {
         "stat.TH".to_owned() => "Statistics Theory",
     ];
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY: &str = include_str!("../../tests/fixtures/arxiv/query.xml");
+    const ERROR: &str = include_str!("../../tests/fixtures/arxiv/error.xml");
+    const EMPTY: &str = include_str!("../../tests/fixtures/arxiv/empty.xml");
+
+    #[test]
+    fn parses_entries() {
+        let feed = parse_feed(QUERY).unwrap();
+        assert_eq!(feed.total_results, Some(1234));
+        assert_eq!(feed.start_index, Some(0));
+        assert_eq!(feed.items_per_page, Some(3));
+        assert_eq!(feed.entries.len(), 3);
+
+        let arxiv = &feed.entries[0];
+        assert_eq!(arxiv.id, "http://arxiv.org/abs/2610.01234v2");
+        assert_eq!(arxiv.published, "2026-10-01T12:00:00Z");
+        assert_eq!(arxiv.updated, "2026-10-15T17:59:59Z");
+        assert_eq!(
+            arxiv.title,
+            "Algebraic Effects & Handlers: A Tutorial on $\\lambda_{\\mathit{eff}}$"
+        );
+        assert_eq!(
+            arxiv.summary,
+            "We present a tutorial on algebraic effects & handlers.\n\
+             The tutorial is self-contained and assumes\n\
+             only familiarity with the lambda calculus."
+        );
+        assert_eq!(arxiv.authors, vec!["Ada Lovelace", "Alan Turing"]);
+        assert_eq!(
+            arxiv.affiliations,
+            vec![
+                vec![
+                    "University of London".to_owned(),
+                    "Analytical Engines Ltd".to_owned()
+                ],
+                vec![]
+            ]
+        );
+        assert_eq!(arxiv.doi.as_deref(), Some("10.1145/1234567.1234568"));
+        assert_eq!(
+            arxiv.journal_ref.as_deref(),
+            Some("Proc. ACM Program. Lang. 10, POPL (2026)")
+        );
+        assert_eq!(arxiv.comment.as_deref(), Some("24 pages, 3 figures"));
+        assert_eq!(arxiv.primary_category, "cs.PL");
+        assert_eq!(arxiv.primary_category_name, "Programming Languages");
+        assert_eq!(arxiv.categories, vec!["cs.PL", "cs.LO"]);
+        assert_eq!(arxiv.pdf_url, "https://arxiv.org/pdf/2610.01234v2.pdf");
+        assert_eq!(arxiv.html_url, "https://arxiv.org/abs/2610.01234v2");
+
+        // links that are https already are kept as they are
+        let arxiv = &feed.entries[1];
+        assert_eq!(arxiv.id, "http://arxiv.org/abs/2609.04321v1");
+        assert_eq!(arxiv.pdf_url, "https://arxiv.org/pdf/2609.04321v1.pdf");
+        assert_eq!(arxiv.html_url, "https://arxiv.org/abs/2609.04321v1");
+
+        let arxiv = &feed.entries[2];
+        assert_eq!(arxiv.id, "http://arxiv.org/abs/hep-th/9901001v1");
+        assert_eq!(arxiv.authors, vec!["Emmy Noether"]);
+        assert_eq!(arxiv.doi, None);
+        assert_eq!(arxiv.comment, None);
+    }
+
+    #[test]
+    fn parses_authors_without_whitespace() {
+        let body = "<feed><entry><id>http://arxiv.org/abs/2610.00001v1</id>\
+                    <author><name>A. Author</name></author>\
+                    <author><name>B. Author</name></author></entry></feed>";
+        let feed = parse_feed(body).unwrap();
+        assert_eq!(feed.entries[0].authors, vec!["A. Author", "B. Author"]);
+        assert_eq!(feed.total_results, None);
+    }
+
+    #[test]
+    fn parses_empty_page() {
+        let feed = parse_feed(EMPTY).unwrap();
+        assert_eq!(feed.total_results, Some(1234));
+        assert_eq!(feed.start_index, Some(5000));
+        assert!(feed.entries.is_empty());
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        match parse_feed(ERROR) {
+            Err(ArxivError::Api { message }) => {
+                assert_eq!(message, "incorrect id format for 1234.1234v1")
+            }
+            result => panic!("expected an API error, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_truncated_responses() {
+        let truncated = &QUERY[..QUERY.find("<entry>").unwrap() + 200];
+        assert!(parse_feed(truncated).is_err());
+        assert!(parse_feed("").is_err());
+    }
+
+    #[test]
+    fn splits_versions() {
+        assert_eq!(split_version("2410.12345v2"), ("2410.12345", Some(2)));
+        assert_eq!(split_version("2410.12345v12"), ("2410.12345", Some(12)));
+        assert_eq!(split_version("2410.12345"), ("2410.12345", None));
+        assert_eq!(
+            split_version("http://arxiv.org/abs/2410.12345v3"),
+            ("2410.12345", Some(3))
+        );
+        assert_eq!(
+            split_version("http://arxiv.org/abs/hep-th/9901001v1"),
+            ("hep-th/9901001", Some(1))
+        );
+        // a `v` that isn't followed by a version
+        assert_eq!(
+            split_version("solv-int/9901001"),
+            ("solv-int/9901001", None)
+        );
+        assert_eq!(split_version("2410.12345v"), ("2410.12345v", None));
+        assert_eq!(split_version("v2"), ("v2", None));
+    }
+
+    async fn fetch_new(
+        max_results: usize,
+        last_published: Option<&str>,
+    ) -> (Vec<String>, Option<String>) {
+        let server = crate::utility::mock_server::mock_server(vec![("/api/query", QUERY)]).await;
+        let query = ArxivQueryBuilder {
+            base_url: format!("{server}/api/query?"),
+            ..ArxivQueryBuilder::default()
+        };
+        let last_published = last_published.and_then(crate::utility::date::parse_date);
+        let new_articles = fetch_new_articles(
+            &Fetcher::new().unwrap(),
+            &query,
+            3,
+            max_results,
+            last_published,
+            std::time::Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        (
+            new_articles
+                .articles
+                .into_iter()
+                .map(|article| article.id)
+                .collect(),
+            new_articles
+                .last_published
+                .map(|time| time.format("%Y-%m-%d").to_string()),
+        )
+    }
+
+    #[tokio::test]
+    async fn fetches_the_newest_articles_on_the_first_run() {
+        let (ids, last_published) = fetch_new(3, None).await;
+        assert_eq!(ids.len(), 3);
+        assert_eq!(last_published.as_deref(), Some("2026-10-01"));
+    }
+
+    #[tokio::test]
+    async fn fetches_articles_since_the_last_run_with_an_overlap() {
+        // the paging reaches the last run, so the watermark advances
+        let (ids, last_published) = fetch_new(10, Some("2026-09-01T00:00:00Z")).await;
+        assert_eq!(
+            ids,
+            [
+                "http://arxiv.org/abs/2610.01234v2",
+                "http://arxiv.org/abs/2609.04321v1"
+            ]
+        );
+        assert_eq!(last_published.as_deref(), Some("2026-10-01"));
+
+        // a paper published a little before the newest one that the last run
+        // saw may have been announced since
+        let (ids, last_published) = fetch_new(10, Some("2026-10-03T00:00:00Z")).await;
+        assert_eq!(ids, ["http://arxiv.org/abs/2610.01234v2"]);
+        assert_eq!(last_published.as_deref(), Some("2026-10-03"));
+    }
+
+    #[tokio::test]
+    async fn only_advances_the_watermark_if_the_last_run_was_reached() {
+        let (ids, last_published) = fetch_new(3, Some("1998-12-31T00:00:00Z")).await;
+        assert_eq!(ids.len(), 3);
+        assert_eq!(last_published.as_deref(), Some("1998-12-31"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Keeping the stored metadata of arXiv papers up to date, so that new versions
+//! of bookmarked papers are noticed.
+use crate::{
+    Env, config,
+    models::{ArxivPaper, Preview},
+    utility::{
+        self,
+        arxiv::{ArxivQueryBuilder, get_id_from_url, split_version},
+    },
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// The most papers that are looked up in one query.
+const MAX_IDS_PER_QUERY: usize = 50;
+
+/// A version of a paper that is newer than the one that was stored.
+#[derive(Debug, Clone)]
+pub struct NewVersion {
+    pub paper: ArxivPaper,
+    pub stored_version: i32,
+}
+
+/// The ids of the arXiv papers of the previews, each once, in the order of the
+/// previews.
+pub fn arxiv_ids_of(previews: &[Preview]) -> Vec<String> {
+    let mut arxiv_ids: Vec<String> = vec![];
+    for arxiv_id in previews
+        .iter()
+        .filter_map(|preview| arxiv_id_of(&preview.url))
+    {
+        if !arxiv_ids.contains(&arxiv_id) {
+            arxiv_ids.push(arxiv_id);
+        }
+    }
+    arxiv_ids
+}
+
+/// The id of the arXiv paper at the URL, without a version.
+pub fn arxiv_id_of(url: &str) -> Option<String> {
+    let (arxiv_id, _) = split_version(get_id_from_url(url)?);
+    Some(arxiv_id.to_owned())
+}
+
+/// Fetches the latest versions of the papers, many per query.
+pub async fn fetch_papers(env: &Env, arxiv_ids: &[String]) -> Result<Vec<ArxivPaper>> {
+    let mut papers = vec![];
+    for (i, arxiv_ids) in arxiv_ids.chunks(MAX_IDS_PER_QUERY).enumerate() {
+        if i > 0 {
+            tokio::time::sleep(config::ARXIV_PAGE_DELAY).await;
+        }
+        let query = ArxivQueryBuilder::new()
+            .id_list(&arxiv_ids.join(","))
+            .max_results(arxiv_ids.len() as i32)
+            .build();
+        for article in utility::arxiv::fetch_arxivs(&env.fetcher, query).await? {
+            papers.push(ArxivPaper::from_arxiv(&article));
+        }
+    }
+    Ok(papers)
+}
+
+/// The stored papers of the previews that are arXiv papers, each once, in the
+/// order of the previews. Papers that aren't stored yet are fetched and stored.
+pub async fn load_papers(env: &mut Env, previews: &[Preview]) -> Result<Vec<ArxivPaper>> {
+    let arxiv_ids = arxiv_ids_of(previews);
+    let mut papers = HashMap::new();
+    let mut missing = vec![];
+    for arxiv_id in &arxiv_ids {
+        match utility::db::get_arxiv_paper(&mut env.db_conn, arxiv_id)? {
+            Some(paper) => {
+                papers.insert(arxiv_id.clone(), paper);
+            }
+            None => missing.push(arxiv_id.clone()),
+        }
+    }
+
+    if !missing.is_empty() {
+        for paper in fetch_papers(env, &missing).await? {
+            if !env.dry_run {
+                utility::db::upsert_arxiv_paper(&mut env.db_conn, &paper)?;
+            }
+            papers.insert(paper.arxiv_id.clone(), paper);
+        }
+    }
+
+    Ok(arxiv_ids
+        .iter()
+        .filter_map(|arxiv_id| {
+            let paper = papers.remove(arxiv_id);
+            if paper.is_none() {
+                log::warn!["no such arXiv paper: {arxiv_id}"];
+            }
+            paper
+        })
+        .collect())
+}
+
+/// Fetches the latest versions of the papers of the previews, and stores those
+/// that are newer than the stored ones. Returns the newer versions of papers
+/// that were already stored.
+pub async fn check_new_versions(env: &mut Env, previews: &[Preview]) -> Result<Vec<NewVersion>> {
+    let arxiv_ids = arxiv_ids_of(previews);
+
+    let mut new_versions = vec![];
+    for paper in fetch_papers(env, &arxiv_ids).await? {
+        let stored_version = if env.dry_run {
+            utility::db::get_arxiv_paper(&mut env.db_conn, &paper.arxiv_id)?
+                .map(|stored| stored.version)
+                .filter(|version| *version < paper.version)
+        } else {
+            utility::db::upsert_arxiv_paper(&mut env.db_conn, &paper)?
+        };
+        if let Some(stored_version) = stored_version {
+            log::info![
+                "new version of {}: v{stored_version} -> v{}",
+                paper.arxiv_id,
+                paper.version
+            ];
+            new_versions.push(NewVersion {
+                paper,
+                stored_version,
+            });
+        }
+    }
+    Ok(new_versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_each_paper_once_in_order() {
+        let previews: Vec<_> = [
+            "https://arxiv.org/abs/2410.00002v2",
+            "https://example.com/",
+            "https://arxiv.org/pdf/2410.00001",
+            "https://arxiv.org/abs/2410.00002",
+        ]
+        .into_iter()
+        .map(|url| Preview::from_url(url.to_owned()))
+        .collect();
+        assert_eq!(arxiv_ids_of(&previews), ["2410.00002", "2410.00001"]);
+    }
+}
@@ -0,0 +1,348 @@
+//! Posts on [Bluesky](https://bsky.app), from the public API of its AppView.
+//!
+//! A post's URL names its author by handle or DID, e.g.
+//! `https://bsky.app/profile/jay.bsky.team/post/3juflvcrbxk2t`. A handle is
+//! first resolved to the DID of its account, since the AT Protocol URI of the
+//! post that `app.bsky.feed.getPostThread` takes is made of the DID.
+use crate::{
+    config,
+    utility::{self, http::Fetcher},
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use url::Url;
+
+/// The default URL of the public API, which the `BLUESKY_API_URL` environment
+/// variable overrides.
+pub const DEFAULT_API_URL: &str = "https://public.api.bsky.app";
+
+/// The hosts that posts are recognized on.
+const HOSTS: [&str; 2] = ["bsky.app", "staging.bsky.app"];
+
+pub fn api_url() -> &'static str {
+    config::BLUESKY_API_URL
+        .as_deref()
+        .unwrap_or(DEFAULT_API_URL)
+}
+
+/// A post, as identified by its URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostRef {
+    /// The handle or DID of the author.
+    pub actor: String,
+    /// The record key of the post.
+    pub rkey: String,
+}
+
+impl PostRef {
+    /// Parses a URL like `https://bsky.app/profile/{actor}/post/{rkey}`.
+    pub fn parse(url: &str) -> Option<PostRef> {
+        let url = Url::parse(url).ok()?;
+        if !HOSTS.contains(&url.host_str()?) {
+            return None;
+        }
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        match segments[..] {
+            ["profile", actor, "post", rkey] => Some(PostRef {
+                actor: actor.to_owned(),
+                rkey: rkey.to_owned(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ResolvedHandle {
+    did: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PostThread {
+    thread: Thread,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Thread {
+    /// Missing when the post was deleted or its author blocks the viewer.
+    post: Option<Post>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Post {
+    /// The AT Protocol URI of the post.
+    pub uri: String,
+    pub author: Author,
+    pub record: Record,
+    /// The images, video, link card or quoted post attached to the post.
+    pub embed: Option<Embed>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Author {
+    pub did: String,
+    pub handle: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Record {
+    pub text: String,
+    pub created_at: String,
+    /// The mentions, links and hashtags in the text.
+    #[serde(default)]
+    pub facets: Vec<Facet>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Facet {
+    pub features: Vec<FacetFeature>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FacetFeature {
+    /// The URL of a link feature.
+    pub uri: Option<String>,
+}
+
+/// A view of an embed of any type. The fields that an embed doesn't have are
+/// left empty, e.g. `images` for anything but `app.bsky.embed.images#view`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Embed {
+    pub images: Vec<EmbedImage>,
+    /// The alt text of a video.
+    pub alt: Option<String>,
+    /// A link card.
+    pub external: Option<EmbedExternal>,
+    /// The media of a quoted post with media.
+    pub media: Option<Box<Embed>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbedImage {
+    #[serde(default)]
+    pub alt: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbedExternal {
+    pub uri: String,
+}
+
+impl Post {
+    pub fn author_name(&self) -> &str {
+        match self.author.display_name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => name,
+            _ => &self.author.handle,
+        }
+    }
+
+    pub fn posted(&self) -> Option<DateTime<Utc>> {
+        utility::date::parse_date(&self.record.created_at)
+    }
+
+    /// The alt texts of the attached images and video.
+    pub fn alt_texts(&self) -> Vec<String> {
+        let mut alt_texts = vec![];
+        let mut embed = self.embed.as_ref();
+        while let Some(e) = embed {
+            for image in &e.images {
+                if !image.alt.trim().is_empty() {
+                    alt_texts.push(format!("image: {}", image.alt.trim()));
+                }
+            }
+            if let Some(alt) = e.alt.as_deref().map(str::trim)
+                && !alt.is_empty()
+            {
+                alt_texts.push(format!("video: {alt}"));
+            }
+            embed = e.media.as_deref();
+        }
+        alt_texts
+    }
+
+    /// The links in the text of the post and the link of its card, in full,
+    /// since the text shortens them.
+    pub fn links(&self) -> Vec<String> {
+        let mut links: Vec<String> = vec![];
+        let facet_links = self
+            .record
+            .facets
+            .iter()
+            .flat_map(|facet| &facet.features)
+            .filter_map(|feature| feature.uri.clone());
+        let mut card_links = vec![];
+        let mut embed = self.embed.as_ref();
+        while let Some(e) = embed {
+            if let Some(external) = &e.external {
+                card_links.push(external.uri.clone());
+            }
+            embed = e.media.as_deref();
+        }
+        for link in facet_links.chain(card_links) {
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+        links
+    }
+}
+
+/// The DID of the account of `actor`, which is either a handle or already a
+/// DID.
+pub async fn resolve_did(fetcher: &Fetcher, api_url: &str, actor: &str) -> Result<String> {
+    if actor.starts_with("did:") {
+        return Ok(actor.to_owned());
+    }
+    let resolved: ResolvedHandle = fetcher
+        .get(&format!(
+            "{api_url}/xrpc/com.atproto.identity.resolveHandle?handle={}",
+            urlencoding::encode(actor)
+        ))
+        .await?
+        .json()?;
+    Ok(resolved.did)
+}
+
+pub async fn fetch_post(fetcher: &Fetcher, api_url: &str, post_ref: &PostRef) -> Result<Post> {
+    let did = resolve_did(fetcher, api_url, &post_ref.actor).await?;
+    let uri = format!("at://{did}/app.bsky.feed.post/{}", post_ref.rkey);
+    let thread: PostThread = fetcher
+        .get(&format!(
+            "{api_url}/xrpc/app.bsky.feed.getPostThread?uri={}&depth=0&parentHeight=0",
+            urlencoding::encode(&uri)
+        ))
+        .await?
+        .json()?;
+    thread
+        .thread
+        .post
+        .ok_or(anyhow!("the Bluesky post is unavailable: {uri}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::mock_server::mock_server;
+
+    const RESOLVED_HANDLE: &str = r#"{"did": "did:plc:ada"}"#;
+
+    const THREAD: &str = r#"{
+        "thread": {
+            "$type": "app.bsky.feed.defs#threadViewPost",
+            "post": {
+                "uri": "at://did:plc:ada/app.bsky.feed.post/3lbtstxtest2a",
+                "cid": "bafyreia",
+                "author": {
+                    "did": "did:plc:ada",
+                    "handle": "ada.bsky.social",
+                    "displayName": "Ada Lovelace"
+                },
+                "record": {
+                    "$type": "app.bsky.feed.post",
+                    "text": "Effects, at last: example.com/effe...",
+                    "createdAt": "2026-10-01T12:34:56.789Z",
+                    "facets": [
+                        {
+                            "index": {"byteStart": 18, "byteEnd": 37},
+                            "features": [{"$type": "app.bsky.richtext.facet#link", "uri": "https://example.com/effects"}]
+                        },
+                        {
+                            "index": {"byteStart": 0, "byteEnd": 7},
+                            "features": [{"$type": "app.bsky.richtext.facet#tag", "tag": "effects"}]
+                        }
+                    ]
+                },
+                "embed": {
+                    "$type": "app.bsky.embed.recordWithMedia#view",
+                    "record": {"record": {"uri": "at://did:plc:bob/app.bsky.feed.post/3lbquoted"}},
+                    "media": {
+                        "$type": "app.bsky.embed.images#view",
+                        "images": [
+                            {"thumb": "https://cdn.bsky.app/a", "fullsize": "https://cdn.bsky.app/b", "alt": "A diagram of a handler"},
+                            {"thumb": "https://cdn.bsky.app/c", "fullsize": "https://cdn.bsky.app/d", "alt": ""}
+                        ]
+                    }
+                },
+                "replyCount": 2,
+                "repostCount": 3,
+                "likeCount": 5
+            }
+        }
+    }"#;
+
+    const NOT_FOUND: &str = r#"{
+        "thread": {
+            "$type": "app.bsky.feed.defs#notFoundPost",
+            "uri": "at://did:plc:ada/app.bsky.feed.post/3lbtstxtest2a",
+            "notFound": true
+        }
+    }"#;
+
+    #[test]
+    fn parses_post_urls() {
+        assert_eq!(
+            PostRef::parse("https://bsky.app/profile/ada.bsky.social/post/3lbtstxtest2a"),
+            Some(PostRef {
+                actor: "ada.bsky.social".to_owned(),
+                rkey: "3lbtstxtest2a".to_owned()
+            })
+        );
+        assert_eq!(
+            PostRef::parse("https://bsky.app/profile/did:plc:ada/post/3lbtstxtest2a")
+                .map(|post_ref| post_ref.actor),
+            Some("did:plc:ada".to_owned())
+        );
+        assert_eq!(
+            PostRef::parse("https://bsky.app/profile/ada.bsky.social"),
+            None
+        );
+        assert_eq!(
+            PostRef::parse("https://example.com/profile/ada/post/3lbtstxtest2a"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_posts() {
+        let api_url = mock_server(vec![
+            ("/xrpc/com.atproto.identity.resolveHandle", RESOLVED_HANDLE),
+            ("/xrpc/app.bsky.feed.getPostThread", THREAD),
+        ])
+        .await;
+        let fetcher = Fetcher::new().unwrap();
+
+        let post_ref =
+            PostRef::parse("https://bsky.app/profile/ada.bsky.social/post/3lbtstxtest2a").unwrap();
+        let post = fetch_post(&fetcher, &api_url, &post_ref).await.unwrap();
+        assert_eq!(post.author_name(), "Ada Lovelace");
+        assert_eq!(post.author.handle, "ada.bsky.social");
+        assert_eq!(post.record.text, "Effects, at last: example.com/effe...");
+        assert_eq!(
+            post.posted().map(|posted| posted.timestamp()),
+            Some(1790858096)
+        );
+        assert_eq!(post.alt_texts(), vec!["image: A diagram of a handler"]);
+        assert_eq!(post.links(), vec!["https://example.com/effects"]);
+    }
+
+    #[tokio::test]
+    async fn fails_for_unavailable_posts() {
+        let fetcher = Fetcher::new().unwrap();
+        let post_ref =
+            PostRef::parse("https://bsky.app/profile/did:plc:ada/post/3lbtstxtest2a").unwrap();
+
+        let api_url = mock_server(vec![("/xrpc/app.bsky.feed.getPostThread", NOT_FOUND)]).await;
+        assert!(fetch_post(&fetcher, &api_url, &post_ref).await.is_err());
+
+        // the handle can't be resolved
+        let api_url = mock_server(vec![("/xrpc/app.bsky.feed.getPostThread", THREAD)]).await;
+        let post_ref =
+            PostRef::parse("https://bsky.app/profile/ada.bsky.social/post/3lbtstxtest2a").unwrap();
+        assert!(fetch_post(&fetcher, &api_url, &post_ref).await.is_err());
+    }
+}
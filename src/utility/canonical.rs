@@ -0,0 +1,559 @@
+//! Canonical forms of URLs, so that the same page is stored only once.
+//!
+//! [`canonicalize_url`] is applied to every new [`Preview`]. It
+//! - lowercases the scheme and host and drops the default port,
+//! - drops the fragment and tracking parameters like `utm_*` and `fbclid`,
+//! - drops the trailing slash of the path,
+//! - resolves the abs, pdf and html pages of an arXiv paper, in any version,
+//!   to its abs page,
+//! - resolves a post on X, on any of its hosts and mirrors, to its page on
+//!   x.com.
+//!
+//! The web embellisher also honors the page's `<link rel="canonical">`, see
+//! [`canonical_link`]. Previews stored before URLs were canonicalized are
+//! merged by [`merge_duplicate_previews`].
+use crate::{
+    models::{Preview, TagProvenance},
+    utility,
+};
+use anyhow::{Result, anyhow};
+use diesel::{
+    prelude::*,
+    sql_types::{Bool, Date, Integer, Nullable, Text, Timestamp},
+};
+use std::{collections::BTreeMap, str::FromStr};
+use url::{Url, form_urlencoded};
+
+/// Query parameters that only track where a link was shared from.
+const TRACKING_PARAMS: [&str; 15] = [
+    "fbclid",
+    "gclid",
+    "dclid",
+    "msclkid",
+    "mc_cid",
+    "mc_eid",
+    "igshid",
+    "yclid",
+    "_hsenc",
+    "_hsmi",
+    "ref_src",
+    "ref_url",
+    "shareimagevariant",
+    "triedredirect",
+    "share_source",
+];
+
+/// Query parameters that only track where a link was shared from when they
+/// appear next to another tracking parameter, since their names are too common
+/// to drop otherwise. For example, Substack's share links have both `r` and
+/// `utm_medium`, while `ref` is also the branch or tag of many code hosts.
+const CONTEXTUAL_TRACKING_PARAMS: [&str; 2] = ["r", "ref"];
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+/// The canonical form of `url`. URLs that can't be parsed are only trimmed.
+pub fn canonicalize_url(url: &str) -> String {
+    let url = url.trim();
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_owned();
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return parsed.to_string();
+    }
+
+    if let Some(arxiv_url) = arxiv_abs_url(&parsed) {
+        return arxiv_url;
+    }
+    if let Some(post_ref) = utility::x::PostRef::parse(parsed.as_str()) {
+        return post_ref.url();
+    }
+
+    parsed.set_fragment(None);
+
+    // query, whose parameters are kept as they are encoded
+    let query = parsed.query().unwrap_or_default().to_owned();
+    let params: Vec<(&str, String)> = query
+        .split("&")
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let name = form_urlencoded::parse(param.as_bytes())
+                .next()
+                .map(|(name, _)| name.into_owned())
+                .unwrap_or_default();
+            (param, name)
+        })
+        .collect();
+    let has_tracking_params = params.iter().any(|(_, name)| is_tracking_param(name));
+    let kept: Vec<&str> = params
+        .iter()
+        .filter(|(_, name)| {
+            let is_contextual = CONTEXTUAL_TRACKING_PARAMS.contains(&name.as_str());
+            !(is_tracking_param(name) || has_tracking_params && is_contextual)
+        })
+        .map(|(param, _)| *param)
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else if kept.len() < params.len() {
+        parsed.set_query(Some(&kept.join("&")));
+    }
+
+    // path
+    let path = parsed.path().to_owned();
+    if path.len() > 1 && path.ends_with("/") {
+        parsed.set_path(path.trim_end_matches("/"));
+    }
+
+    parsed.to_string()
+}
+
+/// The abs page of the arXiv paper at `url`, without its version, e.g.
+/// `https://arxiv.org/abs/2410.12345` for `https://arxiv.org/pdf/2410.12345v2`.
+fn arxiv_abs_url(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    if !matches!(host, "arxiv.org" | "www.arxiv.org" | "export.arxiv.org") {
+        return None;
+    }
+
+    let path = url.path().trim_end_matches("/");
+    let id = ["/abs/", "/pdf/", "/html/"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))?;
+    let id = id.strip_suffix(".pdf").unwrap_or(id);
+    if id.is_empty() {
+        return None;
+    }
+
+    // drop the version, e.g. the `v2` of `2410.12345v2`
+    let (id, _) = utility::arxiv::split_version(id);
+
+    Some(format!("https://arxiv.org/abs/{id}"))
+}
+
+/// The canonical URL that the HTML page at `page_url` declares with
+/// `<link rel="canonical">`, in its canonical form.
+///
+/// A declared URL for the root of a site is ignored unless the page is the
+/// root itself, since misconfigured sites declare their root for every page.
+pub fn canonical_link(html: &str, page_url: &str) -> Option<String> {
+    let document = scraper::Html::parse_document(html);
+    let selector = scraper::Selector::parse("link[rel~=canonical][href]").ok()?;
+    let href = document.select(&selector).next()?.value().attr("href")?;
+
+    let page_url = Url::parse(page_url).ok()?;
+    let url = page_url.join(href.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    if url.path() == "/" && page_url.path() != "/" {
+        return None;
+    }
+
+    Some(canonicalize_url(url.as_str()))
+}
+
+/// Rewrites the URL of every stored preview into its canonical form. Previews
+/// whose canonical URLs are the same are merged into one. Returns the number
+/// of previews that were rewritten or merged.
+pub fn merge_duplicate_previews(db_conn: &mut SqliteConnection, dry_run: bool) -> Result<usize> {
+    let mut groups: BTreeMap<String, Vec<Preview>> = BTreeMap::new();
+    for preview in utility::db::get_all_previews(db_conn)? {
+        groups
+            .entry(canonicalize_url(&preview.url))
+            .or_default()
+            .push(preview);
+    }
+
+    let mut count = 0;
+    for (url, mut previews) in groups {
+        // prefer to keep the preview that already has the canonical URL, and
+        // otherwise the earliest one
+        previews.sort_by(|a, b| {
+            (a.url != url)
+                .cmp(&(b.url != url))
+                .then(a.added_date.cmp(&b.added_date))
+        });
+        let mut previews = previews.into_iter();
+        let Some(first) = previews.next() else {
+            continue;
+        };
+
+        if first.url != url {
+            log::info!["rewrite {} into {url}", first.url];
+            if !dry_run {
+                rename_preview(db_conn, &first.url, &url)?;
+            }
+            count += 1;
+        }
+        for preview in previews {
+            log::info!["merge {} into {url}", preview.url];
+            if !dry_run {
+                merge_preview(db_conn, &preview, &url)?;
+            }
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Changes the URL of a stored preview. The tags and content of the preview
+/// follow it, since their foreign keys cascade on update.
+fn rename_preview(db_conn: &mut SqliteConnection, from: &str, to: &str) -> Result<()> {
+    use crate::schema::previews::dsl;
+
+    diesel::update(dsl::previews.find(from))
+        .set(dsl::url.eq(to))
+        .execute(db_conn)?;
+    Ok(())
+}
+
+/// Merges a preview that was just embellished into the preview that is already
+/// stored at its URL. Embellishing may rewrite the URL of a preview after it
+/// was checked to be new, e.g. into the canonical URL that the page declares,
+/// or into the page that a Hacker News item links to. The flags of the
+/// previews are combined, the missing fields of the stored preview are filled
+/// in, and the tags of the preview are added to it, so that nothing about the
+/// stored preview, like whether it is bookmarked, is lost.
+pub fn merge_into_stored(db_conn: &mut SqliteConnection, preview: &Preview) -> Result<()> {
+    log::info!["merge into the stored {}", preview.url];
+    db_conn.transaction(|conn| {
+        merge_fields(conn, preview, &preview.url)?;
+        let names = utility::tags::normalize_tags(preview.tags.as_deref().unwrap_or_default());
+        if !names.is_empty() {
+            let provenance = match &preview.tags_provenance {
+                None => TagProvenance::Manual,
+                Some(provenance) => TagProvenance::from_str(provenance)?,
+            };
+            utility::tags::add_preview_tags(conn, &preview.url, &names, provenance)?;
+        }
+        Ok(())
+    })
+}
+
+/// Reconciles the stored previews with a preview whose URL embellishing
+/// rewrote from `url`, like a Hacker News item that links to a page, or a page
+/// with a canonical link. `stored` is what was stored at `url`, if anything.
+/// If a preview is already stored at the new URL, the preview and the one at
+/// `url` are merged into it, and the merged preview is returned; otherwise the
+/// one at `url` is moved to the new URL, so that it isn't stored twice.
+pub fn reconcile_rewritten_url(
+    db_conn: &mut SqliteConnection,
+    url: &str,
+    stored: Option<&Preview>,
+    preview: Preview,
+) -> Result<Preview> {
+    if preview.url == url {
+        return Ok(preview);
+    }
+    if !utility::db::is_url_known(db_conn, &preview.url)? {
+        if stored.is_some() {
+            log::info!["rewrite {url} into {}", preview.url];
+            rename_preview(db_conn, url, &preview.url)?;
+        }
+        return Ok(preview);
+    }
+
+    merge_into_stored(db_conn, &preview)?;
+    if let Some(stored) = stored {
+        log::info!["merge {url} into {}", preview.url];
+        merge_preview(db_conn, stored, &preview.url)?;
+    }
+    utility::db::get_preview(db_conn, preview.url.clone())?
+        .ok_or(anyhow!("the merged preview is missing: {}", preview.url))
+}
+
+/// Merges a stored preview into the stored preview at `into`, then deletes it.
+/// The flags of the previews are combined, missing fields are filled in, and
+/// the tags and content are moved over unless `into` already has them.
+pub fn merge_preview(db_conn: &mut SqliteConnection, from: &Preview, into: &str) -> Result<()> {
+    db_conn.transaction(|conn| {
+        merge_fields(conn, from, into)?;
+
+        diesel::sql_query(
+            "INSERT OR IGNORE INTO preview_tags (preview_url, tag_id, provenance) \
+             SELECT ?, tag_id, provenance FROM preview_tags WHERE preview_url = ?",
+        )
+        .bind::<Text, _>(into)
+        .bind::<Text, _>(&from.url)
+        .execute(conn)?;
+
+        diesel::sql_query(
+            "INSERT OR IGNORE INTO preview_contents \
+               (url, content, content_type, byte_length, extraction_method, fetched_at, page_count) \
+             SELECT ?, content, content_type, byte_length, extraction_method, fetched_at, page_count \
+             FROM preview_contents WHERE url = ?",
+        )
+        .bind::<Text, _>(into)
+        .bind::<Text, _>(&from.url)
+        .execute(conn)?;
+
+        utility::db::delete_preview(conn, &from.url)?;
+        utility::tags::refresh_tags_column(conn, into)
+    })
+}
+
+/// Combines the flags of the preview with those of the stored preview at
+/// `into`, and fills in the fields that the stored preview is missing.
+fn merge_fields(db_conn: &mut SqliteConnection, from: &Preview, into: &str) -> Result<()> {
+    diesel::sql_query(
+        "UPDATE previews SET \
+           added_date = min(added_date, ?), \
+           saved = saved OR ?, \
+           embellished = embellished OR ?, \
+           bookmarked = bookmarked OR ?, \
+           source = coalesce(source, ?), \
+           title = coalesce(title, ?), \
+           published_date = coalesce(published_date, ?), \
+           published_at = coalesce(published_at, ?), \
+           summary = coalesce(summary, ?), \
+           author = coalesce(author, ?), \
+           site_name = coalesce(site_name, ?), \
+           image_url = coalesce(image_url, ?), \
+           score = coalesce(score, ?), \
+           comment_count = coalesce(comment_count, ?), \
+           submitter = coalesce(submitter, ?), \
+           discussion_url = coalesce(discussion_url, ?) \
+         WHERE url = ?",
+    )
+    .bind::<Date, _>(from.added_date)
+    .bind::<Bool, _>(from.saved)
+    .bind::<Bool, _>(from.embellished)
+    .bind::<Bool, _>(from.bookmarked)
+    .bind::<Nullable<Text>, _>(&from.source)
+    .bind::<Nullable<Text>, _>(&from.title)
+    .bind::<Nullable<Text>, _>(from.published_date())
+    .bind::<Nullable<Timestamp>, _>(from.published_at)
+    .bind::<Nullable<Text>, _>(&from.summary)
+    .bind::<Nullable<Text>, _>(&from.author)
+    .bind::<Nullable<Text>, _>(&from.site_name)
+    .bind::<Nullable<Text>, _>(&from.image_url)
+    .bind::<Nullable<Integer>, _>(from.score)
+    .bind::<Nullable<Integer>, _>(from.comment_count)
+    .bind::<Nullable<Text>, _>(&from.submitter)
+    .bind::<Nullable<Text>, _>(&from.discussion_url)
+    .bind::<Text, _>(into)
+    .execute(db_conn)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::db;
+
+    #[test]
+    fn canonicalizes_urls() {
+        for (url, canonical) in [
+            ("HTTPS://Example.COM:443/Post/", "https://example.com/Post"),
+            ("http://example.com:8080/", "http://example.com:8080/"),
+            (
+                "https://example.com/post#comments",
+                "https://example.com/post",
+            ),
+            (
+                "https://example.com/post?utm_source=x&id=1&fbclid=abc",
+                "https://example.com/post?id=1",
+            ),
+            (
+                "https://example.com/post?utm_medium=email",
+                "https://example.com/post",
+            ),
+            // contextual parameters are only dropped next to tracking ones
+            (
+                "https://example.substack.com/p/post?r=abc&utm_medium=ios",
+                "https://example.substack.com/p/post",
+            ),
+            (
+                "https://example.com/search?r=1",
+                "https://example.com/search?r=1",
+            ),
+            (
+                "https://gitlab.com/a/b/-/blob/main/README.md?ref=main",
+                "https://gitlab.com/a/b/-/blob/main/README.md?ref=main",
+            ),
+            (
+                "https://example.com/post?ref=hn&utm_source=hn",
+                "https://example.com/post",
+            ),
+            // the parameters that are kept aren't encoded again
+            (
+                "https://example.com/search?q=a%20b&tags=x+y&utm_source=z",
+                "https://example.com/search?q=a%20b&tags=x+y",
+            ),
+            (
+                "https://example.com/search?q=a%20b;c&x",
+                "https://example.com/search?q=a%20b;c&x",
+            ),
+            ("  https://example.com/?  ", "https://example.com/"),
+            ("mailto:someone@example.com", "mailto:someone@example.com"),
+            ("not a url", "not a url"),
+        ] {
+            assert_eq!(canonicalize_url(url), canonical, "{url}");
+        }
+    }
+
+    #[test]
+    fn canonicalizes_arxiv_urls() {
+        for url in [
+            "https://arxiv.org/abs/2410.12345",
+            "https://arxiv.org/abs/2410.12345v2",
+            "https://arxiv.org/pdf/2410.12345v2",
+            "https://arxiv.org/pdf/2410.12345.pdf",
+            "https://www.arxiv.org/html/2410.12345v1/",
+            "http://export.arxiv.org/abs/2410.12345?context=cs",
+        ] {
+            assert_eq!(
+                canonicalize_url(url),
+                "https://arxiv.org/abs/2410.12345",
+                "{url}"
+            );
+        }
+        assert_eq!(
+            canonicalize_url("https://arxiv.org/abs/math/0501001v3"),
+            "https://arxiv.org/abs/math/0501001"
+        );
+        assert_eq!(
+            canonicalize_url("https://arxiv.org/list/cs.PL/recent"),
+            "https://arxiv.org/list/cs.PL/recent"
+        );
+        assert_eq!(
+            arxiv_abs_url(&Url::parse("https://arxiv.org/abs/").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_canonical_links() {
+        let html = |href: &str| {
+            format!("<html><head><link rel=\"canonical\" href=\"{href}\"></head></html>")
+        };
+        assert_eq!(
+            canonical_link(&html("/post?utm_source=x"), "https://example.com/p/1").as_deref(),
+            Some("https://example.com/post")
+        );
+        // a site that declares its root for every page
+        assert_eq!(canonical_link(&html("/"), "https://example.com/post"), None);
+        assert_eq!(
+            canonical_link(&html("javascript:void(0)"), "https://example.com/post"),
+            None
+        );
+        assert_eq!(
+            canonical_link("<html></html>", "https://example.com/post"),
+            None
+        );
+    }
+
+    #[test]
+    fn merges_into_stored_previews() {
+        let mut db_conn = db::test_connection();
+        let mut stored = Preview::from_url("https://example.com/post".to_owned());
+        stored.title = Some("Stored".to_owned());
+        stored.bookmarked = true;
+        stored.tags = Some("rust".to_owned());
+        stored.tags_provenance = Some(TagProvenance::Ai.to_string());
+        db::insert_preview(&mut db_conn, &stored).unwrap();
+
+        // e.g. a page whose canonical URL turned out to be the stored one
+        let mut preview = Preview::from_url(stored.url.clone());
+        preview.title = Some("Fresh".to_owned());
+        preview.summary = Some("A summary".to_owned());
+        preview.saved = true;
+        preview.tags = Some("effects".to_owned());
+        preview.tags_provenance = Some(TagProvenance::Rss.to_string());
+        merge_into_stored(&mut db_conn, &preview).unwrap();
+
+        let merged = db::get_preview(&mut db_conn, stored.url.clone())
+            .unwrap()
+            .unwrap();
+        assert!(merged.bookmarked && merged.saved);
+        assert_eq!(merged.title.as_deref(), Some("Stored"));
+        assert_eq!(merged.summary.as_deref(), Some("A summary"));
+        assert_eq!(merged.tags.as_deref(), Some("effects, rust"));
+    }
+
+    #[test]
+    fn reconciles_hacker_news_items_with_the_pages_they_link_to() {
+        let mut db_conn = db::test_connection();
+        let discussion_url = "https://news.ycombinator.com/item?id=42";
+        let mut item = Preview::from_url(discussion_url.to_owned());
+        item.saved = true;
+        db::insert_preview(&mut db_conn, &item).unwrap();
+        let mut page = Preview::from_url("https://example.com/story".to_owned());
+        page.bookmarked = true;
+        page.tags = Some("rust".to_owned());
+        db::insert_preview(&mut db_conn, &page).unwrap();
+
+        // the item, as the Hacker News embellisher rewrote it
+        let mut preview = item.clone();
+        preview.url = page.url.clone();
+        preview.title = Some("Story".to_owned());
+        preview.score = Some(100);
+        preview.embellished = true;
+        let preview =
+            reconcile_rewritten_url(&mut db_conn, discussion_url, Some(&item), preview).unwrap();
+
+        assert_eq!(preview.url, page.url);
+        assert!(preview.bookmarked && preview.saved && preview.embellished);
+        assert_eq!(preview.score, Some(100));
+        assert_eq!(preview.tags.as_deref(), Some("rust"));
+        assert!(!db::is_url_known(&mut db_conn, discussion_url).unwrap());
+    }
+
+    #[test]
+    fn moves_previews_whose_urls_were_rewritten() {
+        let mut db_conn = db::test_connection();
+        let discussion_url = "https://news.ycombinator.com/item?id=42";
+        let mut item = Preview::from_url(discussion_url.to_owned());
+        item.bookmarked = true;
+        db::insert_preview(&mut db_conn, &item).unwrap();
+
+        let mut preview = item.clone();
+        preview.url = "https://example.com/story".to_owned();
+        let preview =
+            reconcile_rewritten_url(&mut db_conn, discussion_url, Some(&item), preview).unwrap();
+        db::insert_or_update_preview(&mut db_conn, &preview).unwrap();
+
+        let previews = db::get_all_previews(&mut db_conn).unwrap();
+        assert_eq!(previews.len(), 1);
+        assert_eq!(previews[0].url, "https://example.com/story");
+        assert!(previews[0].bookmarked);
+    }
+
+    #[test]
+    fn merges_duplicate_previews() {
+        let mut db_conn = db::test_connection();
+        for (url, bookmarked) in [
+            ("https://example.com/post?utm_source=a", true),
+            ("https://example.com/post/", false),
+            ("https://example.com/other/", false),
+        ] {
+            let mut preview = Preview::from_url(url.to_owned());
+            // as stored before URLs were canonicalized
+            preview.url = url.to_owned();
+            preview.bookmarked = bookmarked;
+            db::insert_preview(&mut db_conn, &preview).unwrap();
+        }
+
+        assert_eq!(merge_duplicate_previews(&mut db_conn, true).unwrap(), 3);
+        assert_eq!(db::get_all_previews(&mut db_conn).unwrap().len(), 3);
+
+        assert_eq!(merge_duplicate_previews(&mut db_conn, false).unwrap(), 3);
+        let previews = db::get_all_previews(&mut db_conn).unwrap();
+        let mut urls: Vec<(&str, bool)> = previews
+            .iter()
+            .map(|preview| (preview.url.as_str(), preview.bookmarked))
+            .collect();
+        urls.sort();
+        assert_eq!(
+            urls,
+            vec![
+                ("https://example.com/other", false),
+                ("https://example.com/post", true)
+            ]
+        );
+    }
+}
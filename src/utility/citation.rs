@@ -0,0 +1,327 @@
+//! Citing arXiv papers in BibTeX and
+//! [CSL-JSON](https://citeproc-js.readthedocs.io/en/latest/csl-json/markup.html),
+//! for writing papers.
+use crate::models::ArxivPaper;
+use anyhow::Result;
+use chrono::Datelike;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitationFormat {
+    Bibtex,
+    CslJson,
+}
+
+impl CitationFormat {
+    pub fn render(&self, papers: &[ArxivPaper]) -> Result<String> {
+        let keys = citation_keys(papers);
+        let cited = papers.iter().zip(&keys);
+        match self {
+            CitationFormat::Bibtex => Ok(cited
+                .map(|(paper, key)| to_bibtex(paper, key))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            CitationFormat::CslJson => {
+                let items: Vec<_> = cited.map(|(paper, key)| to_csl_json(paper, key)).collect();
+                Ok(format!("{}\n", serde_json::to_string_pretty(&items)?))
+            }
+        }
+    }
+}
+
+/// The BibTeX entry of the paper, in the form that arXiv itself exports, with
+/// the given key (see [`citation_keys`]).
+pub fn to_bibtex(paper: &ArxivPaper, key: &str) -> String {
+    let mut fields = vec![
+        ("title", format!("{{{}}}", escape_bibtex(&paper.title))),
+        (
+            "author",
+            paper
+                .authors()
+                .iter()
+                .map(|author| escape_bibtex(author))
+                .collect::<Vec<_>>()
+                .join(" and "),
+        ),
+    ];
+    if let Some(date) = published_date(paper) {
+        fields.push(("year", date.year().to_string()));
+    }
+    fields.push(("eprint", paper.arxiv_id.clone()));
+    fields.push(("archivePrefix", "arXiv".to_owned()));
+    if let Some(primary_category) = &paper.primary_category {
+        fields.push(("primaryClass", primary_category.clone()));
+    }
+    fields.push(("url", paper.url()));
+
+    let fields = fields
+        .into_iter()
+        .map(|(name, value)| format!("  {name} = {{{value}}},\n"))
+        .collect::<String>();
+    format!("@misc{{{key},\n{fields}}}\n")
+}
+
+/// The CSL-JSON item of the paper, with the given key as its id (see
+/// [`citation_keys`]).
+pub fn to_csl_json(paper: &ArxivPaper, key: &str) -> Value {
+    let mut item = json!({
+        "id": key,
+        "type": "article",
+        "title": paper.title,
+        "author": paper.authors().iter().map(|author| csl_name(author)).collect::<Vec<_>>(),
+        "container-title": "arXiv",
+        "publisher": "arXiv",
+        "number": paper.arxiv_id,
+        "version": paper.version.to_string(),
+        // the DOI that arXiv registers for every paper
+        "DOI": format!("10.48550/arXiv.{}", paper.arxiv_id),
+        "URL": paper.url(),
+        "abstract": paper.summary,
+    });
+    if let Some(date) = published_date(paper) {
+        item["issued"] = json!({
+            "date-parts": [[date.year(), date.month(), date.day()]]
+        });
+    }
+    item
+}
+
+/// The citation key of each paper, which are unique among them. Papers whose
+/// [`citation_key`]s are the same are told apart by a letter, in their order,
+/// e.g. `smith2024effectsa` and `smith2024effectsb`, like BibTeX styles do.
+pub fn citation_keys(papers: &[ArxivPaper]) -> Vec<String> {
+    let keys: Vec<String> = papers.iter().map(citation_key).collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for key in &keys {
+        *counts.entry(key).or_default() += 1;
+    }
+
+    // the keys that only one paper has are kept as they are
+    let mut issued: HashSet<String> = keys
+        .iter()
+        .filter(|key| counts[key.as_str()] == 1)
+        .cloned()
+        .collect();
+    let mut suffixes: HashMap<&str, usize> = HashMap::new();
+    keys.iter()
+        .map(|key| {
+            if counts[key.as_str()] == 1 {
+                return key.clone();
+            }
+            loop {
+                let n = suffixes.entry(key).or_default();
+                let suffixed = format!("{key}{}", letters(*n));
+                *n += 1;
+                if issued.insert(suffixed.clone()) {
+                    return suffixed;
+                }
+            }
+        })
+        .collect()
+}
+
+/// The `n`th of `a`, …, `z`, `aa`, `ab`, ….
+fn letters(mut n: usize) -> String {
+    let mut letters = vec![];
+    loop {
+        letters.push(char::from(b'a' + (n % 26) as u8));
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    letters.into_iter().rev().collect()
+}
+
+/// A key like arXiv's, e.g. `smith2024effects`, from the family name of the
+/// first author, the year, and the first long word of the title. Keys are
+/// ASCII, since BibTeX doesn't accept anything else in them.
+fn citation_key(paper: &ArxivPaper) -> String {
+    let family = paper
+        .authors()
+        .first()
+        .and_then(|author| author.split_whitespace().last().map(key_part))
+        .unwrap_or_default();
+    let year = published_date(paper)
+        .map(|date| date.year().to_string())
+        .unwrap_or_default();
+    let word = paper
+        .title
+        .split_whitespace()
+        .map(key_part)
+        .find(|word| word.len() > 3)
+        .unwrap_or_default();
+    let key = format!("{family}{year}{word}");
+    if key.is_empty() {
+        paper.arxiv_id.clone()
+    } else {
+        key
+    }
+}
+
+/// The ASCII letters and digits of the word, lowercased, with the accents of
+/// letters like `é` dropped and any other letters, like `ß`, dropped entirely.
+fn key_part(s: &str) -> String {
+    s.nfd()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn published_date(paper: &ArxivPaper) -> Option<chrono::DateTime<chrono::Utc>> {
+    super::date::parse_date(paper.published.as_deref()?)
+}
+
+/// A name in CSL-JSON, taking the last word of the name as the family name.
+fn csl_name(name: &str) -> Value {
+    match name.trim().rsplit_once(" ") {
+        Some((given, family)) => json!({ "given": given, "family": family }),
+        None => json!({ "literal": name.trim() }),
+    }
+}
+
+/// Escapes the characters that LaTeX treats specially but that are meant
+/// literally in titles and names. Titles on arXiv are already LaTeX, so their
+/// math, like `$\lambda$`, is left alone.
+fn escape_bibtex(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        if matches!(c, '&' | '%' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper() -> ArxivPaper {
+        ArxivPaper {
+            arxiv_id: "2410.12345".to_owned(),
+            version: 2,
+            title: "Effects & Handlers for $\\lambda$-calculi".to_owned(),
+            authors: r#"["Ada B. Lovelace", "Grace Hopper"]"#.to_owned(),
+            summary: "An abstract.".to_owned(),
+            published: Some("2024-10-15T17:00:00Z".to_owned()),
+            updated: None,
+            comment: None,
+            pdf_url: None,
+            primary_category: Some("cs.PL".to_owned()),
+            categories: "cs.PL, cs.LO".to_owned(),
+            fetched_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn renders_bibtex() {
+        assert_eq!(
+            to_bibtex(&paper(), "lovelace2024effects"),
+            "@misc{lovelace2024effects,\n\
+             \x20 title = {{Effects \\& Handlers for $\\lambda$-calculi}},\n\
+             \x20 author = {Ada B. Lovelace and Grace Hopper},\n\
+             \x20 year = {2024},\n\
+             \x20 eprint = {2410.12345},\n\
+             \x20 archivePrefix = {arXiv},\n\
+             \x20 primaryClass = {cs.PL},\n\
+             \x20 url = {https://arxiv.org/abs/2410.12345},\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn renders_csl_json() {
+        let item = to_csl_json(&paper(), "lovelace2024effects");
+        assert_eq!(item["id"], "lovelace2024effects");
+        assert_eq!(item["version"], "2");
+        assert_eq!(item["DOI"], "10.48550/arXiv.2410.12345");
+        assert_eq!(item["issued"]["date-parts"], json!([[2024, 10, 15]]));
+        assert_eq!(
+            item["author"],
+            json!([
+                { "given": "Ada B.", "family": "Lovelace" },
+                { "given": "Grace", "family": "Hopper" },
+            ])
+        );
+    }
+
+    #[test]
+    fn makes_ascii_citation_keys() {
+        let mut paper = paper();
+        paper.authors = r#"["Kurt Gödel"]"#.to_owned();
+        paper.title = "Über formal unentscheidbare Sätze".to_owned();
+        assert_eq!(citation_key(&paper), "godel2024uber");
+
+        paper.authors = r#"["Carl Friedrich Gauß"]"#.to_owned();
+        paper.title = "Théorie".to_owned();
+        assert_eq!(citation_key(&paper), "gau2024theorie");
+
+        // without anything to make a key of, the id is the key
+        paper.authors = r#"["小林"]"#.to_owned();
+        paper.title = "量子".to_owned();
+        paper.published = None;
+        assert_eq!(citation_key(&paper), "2410.12345");
+    }
+
+    #[test]
+    fn tells_colliding_citation_keys_apart() {
+        let mut second = paper();
+        second.arxiv_id = "2411.00001".to_owned();
+        second.title = "Effects for the masses".to_owned();
+        let mut other = paper();
+        other.arxiv_id = "2412.00002".to_owned();
+        other.title = "Handlers in practice".to_owned();
+        let papers = [paper(), other, second];
+        assert_eq!(
+            citation_keys(&papers),
+            [
+                "lovelace2024effectsa",
+                "lovelace2024handlers",
+                "lovelace2024effectsb"
+            ]
+        );
+
+        let bibtex = CitationFormat::Bibtex.render(&papers).unwrap();
+        assert!(bibtex.contains("@misc{lovelace2024effectsa,\n"));
+        assert!(bibtex.contains("@misc{lovelace2024effectsb,\n"));
+        let csl_json: Value =
+            serde_json::from_str(&CitationFormat::CslJson.render(&papers).unwrap()).unwrap();
+        assert_eq!(csl_json[2]["id"], "lovelace2024effectsb");
+
+        // a suffixed key that is already some paper's key is skipped
+        let mut taken = paper();
+        taken.title = "Effectsa".to_owned();
+        let papers = [paper(), paper(), taken];
+        assert_eq!(
+            citation_keys(&papers),
+            [
+                "lovelace2024effectsb",
+                "lovelace2024effectsc",
+                "lovelace2024effectsa"
+            ]
+        );
+        assert_eq!(letters(0), "a");
+        assert_eq!(letters(25), "z");
+        assert_eq!(letters(26), "aa");
+        assert_eq!(letters(27 * 26), "aaa");
+    }
+
+    #[test]
+    fn escapes_bibtex() {
+        assert_eq!(escape_bibtex("R&D: 100% #1"), "R\\&D: 100\\% \\#1");
+        assert_eq!(escape_bibtex("$\\alpha$"), "$\\alpha$");
+    }
+
+    #[test]
+    fn splits_csl_names() {
+        assert_eq!(
+            csl_name(" Ada Lovelace "),
+            json!({ "given": "Ada", "family": "Lovelace" })
+        );
+        assert_eq!(csl_name("Plato"), json!({ "literal": "Plato" }));
+    }
+}
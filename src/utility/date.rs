@@ -0,0 +1,216 @@
+//! Parsing the published dates of previews, which come in whatever format
+//! their source uses: RFC 2822 from RSS, RFC 3339 from arXiv and Atom, and
+//! assorted formats from the metadata of web pages.
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use diesel::{prelude::*, sqlite::SqliteConnection};
+
+/// Formats of times with an offset, after RFC 3339 and RFC 2822.
+const OFFSET_FORMATS: [&str; 5] = [
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%dT%H:%M%z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+    "%Y-%m-%d %H:%M:%S%.f %z",
+    "%a, %d %b %Y %H:%M %z",
+];
+
+/// Formats of times without an offset, which are taken to be in UTC.
+const NAIVE_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Formats of dates, which are taken to be at midnight UTC.
+const DATE_FORMATS: [&str; 8] = [
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y%m%d",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%d %B %Y",
+    "%d %b %Y",
+    "%a, %d %b %Y",
+];
+
+/// Parses a date or time in any of the formats that sources are known to use,
+/// as a time in UTC.
+pub fn parse_date(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+    // offsets that are spelled out, which `%z` doesn't accept
+    let s = match s.strip_suffix(" UTC").or_else(|| s.strip_suffix(" GMT")) {
+        Some(s) if !s.contains(',') => format!("{s}+0000"),
+        _ => s,
+    };
+    let s = s.as_str();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Some(time.to_utc());
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(s) {
+        return Some(time.to_utc());
+    }
+    if let Some(time) = OFFSET_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(s, format).ok())
+    {
+        return Some(time.to_utc());
+    }
+    if let Some(time) = NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+    {
+        return Some(time.and_utc());
+    }
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+}
+
+/// Parses the published date of every stored preview into its published
+/// time, e.g. after the formats that [`parse_date`] knows have changed.
+/// Returns the number of previews whose published time changed.
+pub fn backfill_published_at(db_conn: &mut SqliteConnection, dry_run: bool) -> Result<usize> {
+    use crate::schema::previews::dsl;
+
+    let rows = dsl::previews
+        .filter(dsl::published_date.is_not_null())
+        .select((
+            dsl::url,
+            dsl::published_date.assume_not_null(),
+            dsl::published_at,
+        ))
+        .load(db_conn)?;
+    reparse(db_conn, rows, dry_run, log::Level::Warn)
+}
+
+/// Parses the published dates of the stored previews that don't have a
+/// published time, like those stored before there were published times.
+/// Returns the number of previews whose published time was filled in.
+pub fn backfill_missing_published_at(db_conn: &mut SqliteConnection) -> Result<usize> {
+    use crate::schema::previews::dsl;
+
+    let rows = dsl::previews
+        .filter(dsl::published_date.is_not_null())
+        .filter(dsl::published_at.is_null())
+        .select((
+            dsl::url,
+            dsl::published_date.assume_not_null(),
+            dsl::published_at,
+        ))
+        .load(db_conn)?;
+    // the dates that can't be parsed are tried again on every start, so they
+    // aren't warned about every time
+    reparse(db_conn, rows, false, log::Level::Debug)
+}
+
+fn reparse(
+    db_conn: &mut SqliteConnection,
+    rows: Vec<(String, String, Option<NaiveDateTime>)>,
+    dry_run: bool,
+    unparsable_level: log::Level,
+) -> Result<usize> {
+    use crate::schema::previews::dsl;
+
+    let mut count = 0;
+    for (url, published_date, published_at) in rows {
+        let parsed = parse_date(&published_date).map(|time| time.naive_utc());
+        if parsed.is_none() {
+            log::log![
+                unparsable_level,
+                "unparsable published date of {url}: {published_date:?}"
+            ];
+        }
+        if parsed == published_at {
+            continue;
+        }
+        log::info!["published time of {url}: {parsed:?}"];
+        if !dry_run {
+            diesel::update(dsl::previews.find(&url))
+                .set(dsl::published_at.eq(parsed))
+                .execute(db_conn)?;
+        }
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::Preview, utility::db};
+
+    fn utc(s: &str) -> Option<DateTime<Utc>> {
+        Some(DateTime::parse_from_rfc3339(s).unwrap().to_utc())
+    }
+
+    #[test]
+    fn parses_dates_in_the_formats_of_sources() {
+        for (date, time) in [
+            // RSS
+            ("Sat, 17 Oct 2026 12:30:00 +0200", "2026-10-17T10:30:00Z"),
+            ("Sat, 17 Oct 2026 12:30:00 GMT", "2026-10-17T12:30:00Z"),
+            ("Sat, 17 Oct 2026 12:30 +0000", "2026-10-17T12:30:00Z"),
+            // arXiv and Atom
+            ("2026-10-17T12:30:00Z", "2026-10-17T12:30:00Z"),
+            ("2026-10-17T12:30:00.123-04:00", "2026-10-17T16:30:00.123Z"),
+            // web pages
+            ("2026-10-17T12:30:00+0200", "2026-10-17T10:30:00Z"),
+            ("2026-10-17T12:30+02:00", "2026-10-17T10:30:00Z"),
+            ("2026-10-17 12:30:00 +0200", "2026-10-17T10:30:00Z"),
+            ("2026-10-17 12:30:00 UTC", "2026-10-17T12:30:00Z"),
+            ("2026-10-17T12:30:00", "2026-10-17T12:30:00Z"),
+            ("2026-10-17 12:30", "2026-10-17T12:30:00Z"),
+            ("2026-10-17", "2026-10-17T00:00:00Z"),
+            ("2026/10/17", "2026-10-17T00:00:00Z"),
+            ("20261017", "2026-10-17T00:00:00Z"),
+            ("October 17, 2026", "2026-10-17T00:00:00Z"),
+            ("Oct 17, 2026", "2026-10-17T00:00:00Z"),
+            ("17 October 2026", "2026-10-17T00:00:00Z"),
+            ("  17  Oct\n2026 ", "2026-10-17T00:00:00Z"),
+            ("Sat, 17 Oct 2026", "2026-10-17T00:00:00Z"),
+        ] {
+            assert_eq!(parse_date(date), utc(time), "{date:?}");
+        }
+
+        for date in ["", "yesterday", "2026-13-45", "17/10/2026"] {
+            assert_eq!(parse_date(date), None, "{date:?}");
+        }
+    }
+
+    #[test]
+    fn backfills_missing_published_times() {
+        use crate::schema::previews::dsl;
+
+        let mut conn = db::test_connection();
+        for (url, published_date) in [
+            (
+                "https://example.com/rss",
+                Some("Sat, 17 Oct 2026 12:30:00 GMT"),
+            ),
+            ("https://example.com/unparsable", Some("yesterday")),
+            ("https://example.com/undated", None),
+        ] {
+            let preview = Preview::from_url(url.to_owned());
+            db::insert_preview(&mut conn, &preview).unwrap();
+            // as stored before there were published times
+            diesel::update(dsl::previews.find(url))
+                .set(dsl::published_date.eq(published_date))
+                .execute(&mut conn)
+                .unwrap();
+        }
+
+        assert_eq!(backfill_missing_published_at(&mut conn).unwrap(), 1);
+        assert_eq!(backfill_missing_published_at(&mut conn).unwrap(), 0);
+        let preview = db::get_preview(&mut conn, "https://example.com/rss".to_owned())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            preview.published_at.map(|time| time.and_utc()),
+            utc("2026-10-17T12:30:00Z")
+        );
+    }
+}
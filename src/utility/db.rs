@@ -1,10 +1,139 @@
-use crate::{config, models::*};
-use anyhow::Result;
-use diesel::prelude::*;
+use crate::{models::*, utility};
+use anyhow::{Result, anyhow};
+use diesel::{connection::SimpleConnection, prelude::*};
+use std::{sync::mpsc, thread};
 
-pub fn establish_connection() -> SqliteConnection {
-    SqliteConnection::establish(&config::DATABASE_URL)
-        .unwrap_or_else(|_| panic!("Error connecting to {}", config::DATABASE_URL.as_str()))
+/// How long a connection waits for another connection's write to finish.
+const BUSY_TIMEOUT_MILLIS: u32 = 10_000;
+
+pub fn establish_connection(database_url: &str) -> SqliteConnection {
+    let mut conn = SqliteConnection::establish(database_url)
+        .unwrap_or_else(|_| panic!("Error connecting to {database_url}"));
+    conn.batch_execute(&format!(
+        "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {BUSY_TIMEOUT_MILLIS}"
+    ))
+    .unwrap_or_else(|_| panic!("Error configuring the connection to {database_url}"));
+    conn
+}
+
+/// Fills in what the migrations leave to be computed in Rust, for previews
+/// that were stored before. Does nothing once everything is filled in.
+pub fn backfill(db_conn: &mut SqliteConnection) -> Result<()> {
+    let count = utility::tags::backfill_legacy_tags(db_conn)?;
+    if count > 0 {
+        log::info!["backfilled the tags of {count} previews"];
+    }
+    let count = utility::date::backfill_missing_published_at(db_conn)?;
+    if count > 0 {
+        log::info!["backfilled the published time of {count} previews"];
+    }
+    Ok(())
+}
+
+/// A connection to a new in-memory database with every migration applied.
+#[cfg(test)]
+pub fn test_connection() -> SqliteConnection {
+    let mut conn = establish_connection(":memory:");
+    apply_test_migrations(&mut conn);
+    conn
+}
+
+/// The URL of a new database file in `dirpath` with every migration applied,
+/// for tests that need more than one connection.
+#[cfg(test)]
+pub fn test_database(dirpath: &std::path::Path) -> String {
+    let database_url = dirpath.join("test.db").to_string_lossy().into_owned();
+    apply_test_migrations(&mut establish_connection(&database_url));
+    database_url
+}
+
+#[cfg(test)]
+fn apply_test_migrations(conn: &mut SqliteConnection) {
+    let mut migrations: Vec<_> = std::fs::read_dir("migrations")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    migrations.sort();
+    for migration in migrations {
+        let sql = std::fs::read_to_string(migration.join("up.sql")).unwrap();
+        conn.batch_execute(&sql).unwrap();
+    }
+}
+
+/// A write to the database, queued on a [`DbWriter`].
+#[derive(Debug)]
+pub enum DbWrite {
+    /// Inserts a new preview, and then its content if it has any. A preview
+    /// whose URL turns out to be stored already is merged into the stored one,
+    /// see [`utility::canonical::merge_into_stored`].
+    InsertPreview(Preview, Option<PreviewContent>),
+    UpsertArxivPaper(ArxivPaper),
+    UpsertGithubRepo(GithubRepo),
+}
+
+impl DbWrite {
+    fn apply(self, db_conn: &mut SqliteConnection) -> Result<()> {
+        match self {
+            DbWrite::InsertPreview(preview, content) => {
+                if is_url_known(db_conn, &preview.url)? {
+                    utility::canonical::merge_into_stored(db_conn, &preview)?;
+                } else {
+                    insert_preview(db_conn, &preview)?;
+                }
+                if let Some(content) = content {
+                    insert_or_update_preview_content(db_conn, &content)?;
+                }
+            }
+            DbWrite::UpsertArxivPaper(paper) => {
+                upsert_arxiv_paper(db_conn, &paper)?;
+            }
+            DbWrite::UpsertGithubRepo(repo) => {
+                upsert_github_repo(db_conn, &repo)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies queued writes in order on its own thread, with its own connection,
+/// so that writing doesn't hold up whatever is queuing the writes.
+pub struct DbWriter {
+    sender: mpsc::Sender<DbWrite>,
+    handle: thread::JoinHandle<usize>,
+}
+
+impl DbWriter {
+    pub fn spawn(database_url: &str) -> Self {
+        let database_url = database_url.to_owned();
+        let (sender, receiver) = mpsc::channel::<DbWrite>();
+        let handle = thread::spawn(move || {
+            let mut db_conn = establish_connection(&database_url);
+            let mut failures = 0;
+            for write in receiver {
+                if let Err(e) = write.apply(&mut db_conn) {
+                    log::warn!["Error during DbWrite::apply: {e}"];
+                    failures += 1;
+                }
+            }
+            failures
+        });
+        DbWriter { sender, handle }
+    }
+
+    pub fn write(&self, write: DbWrite) -> Result<()> {
+        self.sender
+            .send(write)
+            .map_err(|_| anyhow!("the database writer has stopped"))
+    }
+
+    /// Waits for the queued writes to be applied. Returns how many failed.
+    pub fn finish(self) -> Result<usize> {
+        drop(self.sender);
+        self.handle
+            .join()
+            .map_err(|_| anyhow!("the database writer panicked"))
+    }
 }
 
 pub fn insert_or_update_preview(db_conn: &mut SqliteConnection, preview: &Preview) -> Result<()> {
@@ -23,6 +152,7 @@ pub fn insert_preview(db_conn: &mut SqliteConnection, preview: &Preview) -> Resu
     diesel::insert_into(dsl::previews)
         .values(preview)
         .execute(db_conn)?;
+    utility::tags::sync_preview_tags(db_conn, preview)?;
     Ok(())
 }
 
@@ -33,13 +163,24 @@ pub fn update_preview(db_conn: &mut SqliteConnection, preview: &Preview) -> Resu
         .set((
             dsl::source.eq(&preview.source),
             dsl::title.eq(&preview.title),
-            dsl::published_date.eq(&preview.published_date),
+            dsl::published_date.eq(preview.published_date()),
+            dsl::published_at.eq(&preview.published_at),
+            dsl::score.eq(&preview.score),
+            dsl::comment_count.eq(&preview.comment_count),
+            dsl::submitter.eq(&preview.submitter),
+            dsl::discussion_url.eq(&preview.discussion_url),
             dsl::tags.eq(&preview.tags),
+            dsl::tags_provenance.eq(&preview.tags_provenance),
             dsl::summary.eq(&preview.summary),
+            dsl::author.eq(&preview.author),
+            dsl::site_name.eq(&preview.site_name),
+            dsl::image_url.eq(&preview.image_url),
+            dsl::saved.eq(&preview.saved),
             dsl::embellished.eq(&preview.embellished),
             dsl::bookmarked.eq(&preview.bookmarked),
         ))
         .execute(db_conn)?;
+    utility::tags::sync_preview_tags(db_conn, preview)?;
     Ok(())
 }
 
@@ -53,6 +194,130 @@ pub fn get_preview(db_conn: &mut SqliteConnection, url: String) -> Result<Option
         .optional()?)
 }
 
+pub fn insert_or_update_preview_content(
+    db_conn: &mut SqliteConnection,
+    content: &PreviewContent,
+) -> Result<()> {
+    use crate::schema::preview_contents::dsl;
+
+    diesel::replace_into(dsl::preview_contents)
+        .values(content)
+        .execute(db_conn)?;
+    Ok(())
+}
+
+/// Stores the arXiv paper, replacing what is stored about any earlier version of
+/// it. Returns the version that was stored before, if it was an earlier one.
+pub fn upsert_arxiv_paper(
+    db_conn: &mut SqliteConnection,
+    paper: &ArxivPaper,
+) -> Result<Option<i32>> {
+    use crate::schema::arxiv_papers::dsl;
+
+    let stored_version: Option<i32> = dsl::arxiv_papers
+        .find(&paper.arxiv_id)
+        .select(dsl::version)
+        .first(db_conn)
+        .optional()?;
+    if stored_version.is_some_and(|version| version > paper.version) {
+        return Ok(None);
+    }
+
+    diesel::replace_into(dsl::arxiv_papers)
+        .values(paper)
+        .execute(db_conn)?;
+    Ok(stored_version.filter(|version| *version < paper.version))
+}
+
+pub fn get_arxiv_paper(
+    db_conn: &mut SqliteConnection,
+    arxiv_id: &str,
+) -> Result<Option<ArxivPaper>> {
+    use crate::schema::arxiv_papers::dsl;
+
+    Ok(dsl::arxiv_papers
+        .find(arxiv_id)
+        .select(ArxivPaper::as_select())
+        .first(db_conn)
+        .optional()?)
+}
+
+pub fn upsert_github_repo(db_conn: &mut SqliteConnection, repo: &GithubRepo) -> Result<()> {
+    use crate::schema::github_repos::dsl;
+
+    diesel::replace_into(dsl::github_repos)
+        .values(repo)
+        .execute(db_conn)?;
+    Ok(())
+}
+
+/// How far the feed has read its source, if it has been run before.
+pub fn get_feed_watermark(
+    db_conn: &mut SqliteConnection,
+    feed_name: &str,
+) -> Result<Option<String>> {
+    use crate::schema::feed_state::dsl;
+
+    Ok(dsl::feed_state
+        .find(feed_name)
+        .select(dsl::watermark)
+        .first(db_conn)
+        .optional()?)
+}
+
+pub fn set_feed_watermark(
+    db_conn: &mut SqliteConnection,
+    feed_name: &str,
+    watermark: &str,
+) -> Result<()> {
+    use crate::schema::feed_state::dsl;
+
+    diesel::replace_into(dsl::feed_state)
+        .values(&FeedState {
+            feed_name: feed_name.to_owned(),
+            watermark: watermark.to_owned(),
+            updated_at: chrono::Utc::now().naive_utc(),
+        })
+        .execute(db_conn)?;
+    Ok(())
+}
+
+pub fn get_preview_content(
+    db_conn: &mut SqliteConnection,
+    url: &str,
+) -> Result<Option<PreviewContent>> {
+    use crate::schema::preview_contents::dsl::preview_contents;
+
+    Ok(preview_contents
+        .find(url)
+        .select(PreviewContent::as_select())
+        .first(db_conn)
+        .optional()?)
+}
+
+/// Loads the stored contents of the previews, by their URLs. Previews without
+/// any content are left out.
+pub fn get_preview_contents(
+    db_conn: &mut SqliteConnection,
+    urls: &[String],
+) -> Result<std::collections::HashMap<String, PreviewContent>> {
+    use crate::schema::preview_contents::dsl;
+
+    Ok(dsl::preview_contents
+        .filter(dsl::url.eq_any(urls))
+        .select(PreviewContent::as_select())
+        .load(db_conn)?
+        .into_iter()
+        .map(|content| (content.url.clone(), content))
+        .collect())
+}
+
+pub fn delete_preview(db_conn: &mut SqliteConnection, url: &str) -> Result<bool> {
+    use crate::schema::previews::dsl::previews;
+
+    Ok(diesel::delete(previews.find(url)).execute(db_conn)? > 0)
+}
+
 pub fn get_all_previews(db_conn: &mut SqliteConnection) -> Result<Vec<Preview>> {
     use crate::schema::previews::dsl::previews;
 
@@ -68,3 +333,106 @@ pub fn is_url_known(db_conn: &mut SqliteConnection, url: &str) -> Result<bool> {
         .optional()?
         .is_some())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{embellisher::ExtractedContent, utility::search};
+
+    #[test]
+    fn stores_and_indexes_preview_contents() {
+        let mut db_conn = test_connection();
+        let preview = Preview::from_url("https://example.com/".to_owned());
+        insert_preview(&mut db_conn, &preview).unwrap();
+        let searched = |db_conn: &mut SqliteConnection, query: &str| {
+            search::search_previews(db_conn, query, &search::SearchFilters::default())
+                .unwrap()
+                .len()
+        };
+
+        let content =
+            ExtractedContent::new("Algebraic effects".to_owned(), "text/html", "readability");
+        insert_or_update_preview_content(
+            &mut db_conn,
+            &PreviewContent::new(preview.url.clone(), content),
+        )
+        .unwrap();
+        let stored = get_preview_content(&mut db_conn, &preview.url)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.content, "Algebraic effects");
+        assert_eq!(stored.byte_length, 17);
+        assert_eq!(stored.content_type.as_deref(), Some("text/html"));
+        assert_eq!(stored.extraction_method, "readability");
+        assert_eq!(searched(&mut db_conn, "algebraic"), 1);
+
+        let content = ExtractedContent::new("Monads".to_owned(), "text/plain", "markdown");
+        insert_or_update_preview_content(
+            &mut db_conn,
+            &PreviewContent::new(preview.url.clone(), content),
+        )
+        .unwrap();
+        let stored = get_preview_content(&mut db_conn, &preview.url)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.content, "Monads");
+        assert_eq!(searched(&mut db_conn, "algebraic"), 0);
+        assert_eq!(searched(&mut db_conn, "monads"), 1);
+
+        // the content goes with its preview
+        delete_preview(&mut db_conn, &preview.url).unwrap();
+        assert!(
+            get_preview_content(&mut db_conn, &preview.url)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    fn paper(version: i32, title: &str) -> ArxivPaper {
+        ArxivPaper {
+            arxiv_id: "2410.12345".to_owned(),
+            version,
+            title: title.to_owned(),
+            authors: "[]".to_owned(),
+            summary: String::new(),
+            published: None,
+            updated: None,
+            comment: None,
+            pdf_url: None,
+            primary_category: None,
+            categories: String::new(),
+            fetched_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn upserts_newer_versions_of_arxiv_papers() {
+        let mut conn = test_connection();
+        let title = |conn: &mut SqliteConnection| {
+            get_arxiv_paper(conn, "2410.12345").unwrap().unwrap().title
+        };
+
+        assert_eq!(
+            upsert_arxiv_paper(&mut conn, &paper(2, "v2")).unwrap(),
+            None
+        );
+        // the same version is refreshed, but isn't a new one
+        assert_eq!(
+            upsert_arxiv_paper(&mut conn, &paper(2, "v2 again")).unwrap(),
+            None
+        );
+        assert_eq!(title(&mut conn), "v2 again");
+        // an earlier version is ignored
+        assert_eq!(
+            upsert_arxiv_paper(&mut conn, &paper(1, "v1")).unwrap(),
+            None
+        );
+        assert_eq!(title(&mut conn), "v2 again");
+        // a later version replaces the stored one
+        assert_eq!(
+            upsert_arxiv_paper(&mut conn, &paper(3, "v3")).unwrap(),
+            Some(2)
+        );
+        assert_eq!(title(&mut conn), "v3");
+    }
+}
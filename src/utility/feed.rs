@@ -0,0 +1,345 @@
+//! Rendering previews as feeds in RSS 2.0, Atom 1.0 and JSON Feed 1.1.
+use crate::{
+    config,
+    models::{Preview, PreviewContent},
+    utility,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs};
+
+/// The stored contents of previews, by their URLs.
+pub type Contents = HashMap<String, PreviewContent>;
+
+/// What every format of a feed has in common.
+#[derive(Debug, Clone)]
+pub struct FeedMeta {
+    pub title: String,
+    pub description: String,
+    /// The file name of each format of the feed, without its extension.
+    pub stem: String,
+}
+
+impl FeedMeta {
+    /// The published URL of the feed in the given format.
+    pub fn url(&self, format: FeedFormat) -> String {
+        format!("{}/{}", config::SITE_URL, format.filename(&self.stem))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    JsonFeed,
+}
+
+impl FeedFormat {
+    pub const ALL: [FeedFormat; 3] = [FeedFormat::Rss, FeedFormat::Atom, FeedFormat::JsonFeed];
+
+    pub fn filename(&self, stem: &str) -> String {
+        match self {
+            FeedFormat::Rss => format!("{stem}.feed.xml"),
+            FeedFormat::Atom => format!("{stem}.atom.xml"),
+            FeedFormat::JsonFeed => format!("{stem}.feed.json"),
+        }
+    }
+
+    /// Renders the previews in this format. The formats that carry the full
+    /// content of an item, Atom and JSON Feed, use the stored content of each
+    /// preview that has one, and otherwise its summary.
+    pub fn render(
+        &self,
+        meta: &FeedMeta,
+        previews: &[Preview],
+        contents: &Contents,
+    ) -> Result<String> {
+        match self {
+            FeedFormat::Rss => {
+                let channel = utility::rss::create_rss_channel(
+                    &meta.title,
+                    &meta.description,
+                    previews.to_vec(),
+                );
+                let bytes = channel.pretty_write_to(vec![], b' ', 4)?;
+                Ok(String::from_utf8(bytes)?)
+            }
+            FeedFormat::Atom => render_atom(meta, previews, contents),
+            FeedFormat::JsonFeed => render_json_feed(meta, previews, contents),
+        }
+    }
+}
+
+/// Writes the feed into `dirpath` in each of the formats.
+pub fn write_feed(
+    dirpath: &str,
+    meta: &FeedMeta,
+    previews: &[Preview],
+    contents: &Contents,
+    formats: &[FeedFormat],
+) -> Result<()> {
+    for format in formats {
+        let file_path = [dirpath, &format.filename(&meta.stem)].join("/");
+        fs::write(file_path, format.render(meta, previews, contents)?)?;
+    }
+    Ok(())
+}
+
+/// When the preview was last updated, i.e. when it was published, or else
+/// when it was added.
+fn updated(preview: &Preview) -> DateTime<Utc> {
+    preview.published_or_added()
+}
+
+fn feed_updated(previews: &[Preview]) -> DateTime<Utc> {
+    previews.iter().map(updated).max().unwrap_or_else(Utc::now)
+}
+
+fn tags(preview: &Preview) -> Vec<String> {
+    preview
+        .tags()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_owned())
+        .collect()
+}
+
+/// The stored content of the preview, if it has any.
+fn content<'a>(preview: &Preview, contents: &'a Contents) -> Option<&'a str> {
+    contents
+        .get(&preview.url)
+        .map(|content| content.content.as_str())
+        .filter(|content| !content.trim().is_empty())
+}
+
+fn render_atom(meta: &FeedMeta, previews: &[Preview], contents: &Contents) -> Result<String> {
+    use atom_syndication::{Category, Content, Entry, Feed, Generator, Link, Person, Text};
+
+    let author = Person {
+        name: "linkstitcher".to_owned(),
+        email: None,
+        uri: Some(config::REPOSITORY_URL.to_owned()),
+    };
+    let entries = previews
+        .iter()
+        .map(|preview| Entry {
+            id: preview.url.clone(),
+            title: Text::plain(preview.title.clone().unwrap_or(preview.url.clone())),
+            updated: updated(preview).fixed_offset(),
+            published: preview
+                .published_at
+                .map(|time| time.and_utc().fixed_offset()),
+            categories: tags(preview)
+                .into_iter()
+                .map(|tag| Category {
+                    term: tag,
+                    ..Default::default()
+                })
+                .collect(),
+            links: vec![Link {
+                href: preview.url.clone(),
+                ..Default::default()
+            }],
+            summary: preview.summary.clone().map(Text::plain),
+            content: content(preview, contents).map(|content| Content {
+                value: Some(content.to_owned()),
+                content_type: Some("text".to_owned()),
+                ..Default::default()
+            }),
+            authors: preview
+                .author
+                .iter()
+                .map(|name| Person {
+                    name: name.clone(),
+                    email: None,
+                    uri: None,
+                })
+                .collect(),
+            ..Default::default()
+        })
+        .collect();
+
+    let feed = Feed {
+        id: meta.url(FeedFormat::Atom),
+        title: Text::plain(meta.title.clone()),
+        subtitle: Some(Text::plain(meta.description.clone())),
+        updated: feed_updated(previews).fixed_offset(),
+        authors: vec![author],
+        links: vec![
+            Link {
+                href: meta.url(FeedFormat::Atom),
+                rel: "self".to_owned(),
+                mime_type: Some("application/atom+xml".to_owned()),
+                ..Default::default()
+            },
+            Link {
+                href: config::REPOSITORY_URL.to_owned(),
+                ..Default::default()
+            },
+        ],
+        generator: Some(Generator {
+            value: "linkstitcher".to_owned(),
+            uri: Some(config::REPOSITORY_URL.to_owned()),
+            version: None,
+        }),
+        entries,
+        ..Default::default()
+    };
+
+    let bytes = feed.write_with_config(
+        vec![],
+        atom_syndication::WriteConfig {
+            write_document_declaration: true,
+            indent_size: Some(4),
+        },
+    )?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// A feed in the [JSON Feed 1.1](https://www.jsonfeed.org/version/1.1/) format.
+#[derive(Debug, Serialize)]
+struct JsonFeed {
+    version: &'static str,
+    title: String,
+    home_page_url: String,
+    feed_url: String,
+    description: String,
+    authors: Vec<JsonFeedAuthor>,
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedAuthor {
+    name: String,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedItemAuthor {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonFeedItem {
+    id: String,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    content_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    date_modified: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authors: Vec<JsonFeedItemAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+fn render_json_feed(meta: &FeedMeta, previews: &[Preview], contents: &Contents) -> Result<String> {
+    let feed = JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: meta.title.clone(),
+        home_page_url: config::REPOSITORY_URL.to_owned(),
+        feed_url: meta.url(FeedFormat::JsonFeed),
+        description: meta.description.clone(),
+        authors: vec![JsonFeedAuthor {
+            name: "linkstitcher".to_owned(),
+            url: config::REPOSITORY_URL.to_owned(),
+        }],
+        items: previews
+            .iter()
+            .map(|preview| JsonFeedItem {
+                id: preview.url.clone(),
+                url: preview.url.clone(),
+                title: preview.title.clone(),
+                content_text: content(preview, contents)
+                    .map(|content| content.to_owned())
+                    .or(preview.summary.clone())
+                    .or(preview.title.clone())
+                    .unwrap_or(preview.url.clone()),
+                summary: preview.summary.clone(),
+                image: preview.image_url.clone(),
+                date_published: preview.published_at.map(|time| time.and_utc().to_rfc3339()),
+                date_modified: updated(preview).to_rfc3339(),
+                authors: preview
+                    .author
+                    .iter()
+                    .map(|name| JsonFeedItemAuthor { name: name.clone() })
+                    .collect(),
+                tags: tags(preview),
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&feed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn previews_and_contents() -> (Vec<Preview>, Contents) {
+        let mut with_content = Preview::from_url("https://example.com/a".to_owned());
+        with_content.title = Some("A".to_owned());
+        with_content.summary = Some("The summary of A".to_owned());
+        let mut without_content = Preview::from_url("https://example.com/b".to_owned());
+        without_content.summary = Some("The summary of B".to_owned());
+
+        let content = PreviewContent::new(
+            with_content.url.clone(),
+            crate::embellisher::ExtractedContent::new(
+                "The full text of A".to_owned(),
+                "text/plain",
+                "test",
+            ),
+        );
+        let contents = Contents::from([(content.url.clone(), content)]);
+        (vec![with_content, without_content], contents)
+    }
+
+    fn meta() -> FeedMeta {
+        FeedMeta {
+            title: "test".to_owned(),
+            description: "A test feed".to_owned(),
+            stem: "test".to_owned(),
+        }
+    }
+
+    #[test]
+    fn renders_stored_contents_in_atom() {
+        let (previews, contents) = previews_and_contents();
+        let atom = FeedFormat::Atom
+            .render(&meta(), &previews, &contents)
+            .unwrap();
+        let feed = atom_syndication::Feed::read_from(atom.as_bytes()).unwrap();
+        let content = |i: usize| {
+            feed.entries()[i]
+                .content()
+                .and_then(|content| content.value())
+                .map(|value| value.to_owned())
+        };
+        assert_eq!(content(0).as_deref(), Some("The full text of A"));
+        assert_eq!(content(1), None);
+        assert_eq!(
+            feed.entries()[1].summary().map(|summary| summary.as_str()),
+            Some("The summary of B")
+        );
+    }
+
+    #[test]
+    fn renders_stored_contents_in_json_feed() {
+        let (previews, contents) = previews_and_contents();
+        let json = FeedFormat::JsonFeed
+            .render(&meta(), &previews, &contents)
+            .unwrap();
+        let feed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(feed["items"][0]["content_text"], "The full text of A");
+        assert_eq!(feed["items"][1]["content_text"], "The summary of B");
+    }
+}
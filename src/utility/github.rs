@@ -1,30 +1,757 @@
+use crate::models::GithubRepo;
 use anyhow::{Result, anyhow};
-use octocrab::Octocrab;
+use chrono::{DateTime, Utc};
+use octocrab::{
+    Octocrab,
+    models::{IssueState, repos::Release},
+};
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use serde_json::json;
 use url::Url;
 
+/// What a GitHub URL is the page of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GithubUrl {
+    Repo {
+        owner: String,
+        repo: String,
+    },
+    Issue {
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+    PullRequest {
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+    /// A release, or the latest release without a tag.
+    Release {
+        owner: String,
+        repo: String,
+        tag: Option<String>,
+    },
+    /// A file at a branch, tag or commit.
+    Blob {
+        owner: String,
+        repo: String,
+        reference: String,
+        path: String,
+    },
+    Discussion {
+        owner: String,
+        repo: String,
+        number: u64,
+    },
+    Gist {
+        id: String,
+    },
+}
+
+/// The first segments of paths on github.com that aren't owners.
+const RESERVED_OWNERS: &[&str] = &[
+    "about",
+    "collections",
+    "enterprise",
+    "explore",
+    "features",
+    "login",
+    "marketplace",
+    "notifications",
+    "orgs",
+    "pricing",
+    "search",
+    "settings",
+    "sponsors",
+    "topics",
+    "trending",
+];
+
+impl GithubUrl {
+    /// Classifies a GitHub URL by its path. Paths under a repository that
+    /// aren't one of the other kinds, like `/{owner}/{repo}/tree/main`, are
+    /// the repository's. Returns `None` for URLs that aren't about a
+    /// repository or gist, like profiles.
+    pub fn parse(url: &str) -> Option<GithubUrl> {
+        let url = Url::parse(url).ok()?;
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        match url.host_str()? {
+            // `/{id}` or `/{owner}/{id}`, where a single segment that isn't an
+            // id is the owner's profile
+            "gist.github.com" => match segments[..] {
+                [id] | [_, id, ..] if is_gist_id(id) => Some(GithubUrl::Gist { id: id.to_owned() }),
+                _ => None,
+            },
+            "github.com" | "www.github.com" => {
+                let [owner, repo, rest @ ..] = &segments[..] else {
+                    return None;
+                };
+                if RESERVED_OWNERS.contains(owner) {
+                    return None;
+                }
+                let (owner, repo) = (
+                    owner.to_string(),
+                    repo.strip_suffix(".git").unwrap_or(repo).to_owned(),
+                );
+                let number = |s: &str| s.parse::<u64>().ok();
+                Some(match rest {
+                    ["issues", n, ..] if number(n).is_some() => GithubUrl::Issue {
+                        owner,
+                        repo,
+                        number: number(n)?,
+                    },
+                    ["pull", n, ..] if number(n).is_some() => GithubUrl::PullRequest {
+                        owner,
+                        repo,
+                        number: number(n)?,
+                    },
+                    ["discussions", n, ..] if number(n).is_some() => GithubUrl::Discussion {
+                        owner,
+                        repo,
+                        number: number(n)?,
+                    },
+                    ["releases", "tag", tag @ ..] if !tag.is_empty() => GithubUrl::Release {
+                        owner,
+                        repo,
+                        tag: Some(tag.join("/")),
+                    },
+                    ["releases"] | ["releases", "latest"] => GithubUrl::Release {
+                        owner,
+                        repo,
+                        tag: None,
+                    },
+                    ["blob", reference, path @ ..] if !path.is_empty() => GithubUrl::Blob {
+                        owner,
+                        repo,
+                        reference: reference.to_string(),
+                        path: path.join("/"),
+                    },
+                    _ => GithubUrl::Repo { owner, repo },
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The `source` of previews of this kind of page.
+    pub fn source(&self) -> &'static str {
+        match self {
+            GithubUrl::Repo { .. } => "GitHub",
+            GithubUrl::Issue { .. } => "GitHub Issue",
+            GithubUrl::PullRequest { .. } => "GitHub Pull Request",
+            GithubUrl::Release { .. } => "GitHub Release",
+            GithubUrl::Blob { .. } => "GitHub File",
+            GithubUrl::Discussion { .. } => "GitHub Discussion",
+            GithubUrl::Gist { .. } => "GitHub Gist",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RepoInfo {
     pub owner: String,
     pub name: String,
+    pub description: Option<String>,
+    pub stars: Option<u32>,
+    pub forks: Option<u32>,
+    /// The primary language, e.g. `Rust`.
+    pub language: Option<String>,
+    pub topics: Vec<String>,
+    /// The SPDX id of the license, e.g. `MIT`.
+    pub license: Option<String>,
+    pub pushed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub archived: bool,
+    /// The README, as markdown.
     pub readme: Option<String>,
 }
 
-pub async fn fetch_repo_info(octocrab: &Octocrab, url_str: &str) -> Result<RepoInfo> {
-    let parsed_url = Url::parse(url_str)?;
-    let mut segments = parsed_url
-        .path_segments()
-        .ok_or_else(|| anyhow!("Invalid URL: cannot get path segments"))?;
-    let owner = segments
-        .next()
-        .ok_or_else(|| anyhow!("Invalid URL: missing owner"))?;
-    let repo_name = segments
-        .next()
-        .ok_or_else(|| anyhow!("Invalid URL: missing repo name"))?;
-    let readme = octocrab.repos(owner, repo_name).get_readme().send().await?;
+impl RepoInfo {
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.owner, self.name)
+    }
+
+    /// `owner/name: description`, or just `owner/name` without a description.
+    pub fn title(&self) -> String {
+        match self.description.as_deref().map(str::trim) {
+            Some(description) if !description.is_empty() => {
+                format!("{}: {description}", self.full_name())
+            }
+            _ => self.full_name(),
+        }
+    }
+
+    pub fn to_github_repo(&self) -> GithubRepo {
+        GithubRepo {
+            full_name: self.full_name(),
+            owner: self.owner.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            stars: self.stars.map(|stars| stars as i32),
+            forks: self.forks.map(|forks| forks as i32),
+            language: self.language.clone(),
+            topics: self.topics.join(", "),
+            license: self.license.clone(),
+            pushed_at: self.pushed_at.map(|time| time.naive_utc()),
+            archived: self.archived,
+            fetched_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
+/// The `rust-lang/rust` repository with the given description and README,
+/// for tests.
+#[cfg(test)]
+pub fn test_repo_info(description: Option<&str>, readme: Option<&str>) -> RepoInfo {
+    RepoInfo {
+        owner: "rust-lang".to_owned(),
+        name: "rust".to_owned(),
+        description: description.map(str::to_owned),
+        stars: Some(100),
+        forks: None,
+        language: Some("Rust".to_owned()),
+        topics: vec!["compiler".to_owned()],
+        license: None,
+        pushed_at: None,
+        archived: false,
+        readme: readme.map(str::to_owned),
+    }
+}
+
+pub async fn fetch_repo_info(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo_name: &str,
+) -> Result<RepoInfo> {
+    let repo = octocrab.repos(owner, repo_name).get().await?;
+    // not every repository has a README
+    let readme = match octocrab.repos(owner, repo_name).get_readme().send().await {
+        Ok(readme) => readme.decoded_content(),
+        Err(e) => {
+            log::warn!["failed to fetch the README of {owner}/{repo_name}: {e}"];
+            None
+        }
+    };
 
     Ok(RepoInfo {
-        owner: owner.to_owned(),
-        name: repo_name.to_owned(),
-        readme: readme.decoded_content(),
+        owner: repo
+            .owner
+            .map(|owner| owner.login)
+            .unwrap_or(owner.to_owned()),
+        name: repo.name,
+        description: repo.description,
+        stars: repo.stargazers_count,
+        forks: repo.forks_count,
+        language: repo
+            .language
+            .as_ref()
+            .and_then(|language| language.as_str())
+            .map(str::to_owned),
+        topics: repo.topics.unwrap_or_default(),
+        license: repo.license.map(|license| {
+            // GitHub's id of licenses that it doesn't recognize
+            if license.spdx_id == "NOASSERTION" {
+                license.name
+            } else {
+                license.spdx_id
+            }
+        }),
+        pushed_at: repo.pushed_at,
+        archived: repo.archived.unwrap_or(false),
+        readme,
     })
 }
+
+/// A GitHub page other than a repository, like an issue or a file.
+#[derive(Debug)]
+pub struct PageInfo {
+    pub title: String,
+    /// The login of whoever opened, published or created it.
+    pub author: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    /// Labels, or the languages of files.
+    pub tags: Vec<String>,
+    pub comment_count: Option<u32>,
+    /// The plain text of the page, like the body of an issue rendered from
+    /// markdown, or the contents of a file.
+    pub text: Option<String>,
+}
+
+impl PageInfo {
+    pub fn from_release(owner: &str, repo: &str, release: Release) -> Self {
+        PageInfo {
+            title: page_title(
+                &format!("{owner}/{repo} {}", release.tag_name),
+                release.name.as_deref().unwrap_or_default(),
+                release.prerelease.then_some("pre-release"),
+            ),
+            author: release.author.map(|author| author.login),
+            published_at: release.published_at,
+            tags: vec![],
+            comment_count: None,
+            text: release.body.as_deref().map(markdown_to_text),
+        }
+    }
+}
+
+/// Fetches the page at a GitHub URL that isn't a repository's.
+pub async fn fetch_page_info(octocrab: &Octocrab, github_url: &GithubUrl) -> Result<PageInfo> {
+    match github_url {
+        GithubUrl::Repo { owner, repo } => Err(anyhow!("{owner}/{repo} is a repository")),
+        GithubUrl::Issue {
+            owner,
+            repo,
+            number,
+        } => {
+            let issue = octocrab.issues(owner, repo).get(*number).await?;
+            Ok(PageInfo {
+                title: page_title(
+                    &format!("{owner}/{repo}#{number}"),
+                    &issue.title,
+                    (issue.state == IssueState::Closed).then_some("closed"),
+                ),
+                author: Some(issue.user.login),
+                published_at: Some(issue.created_at),
+                tags: issue.labels.into_iter().map(|label| label.name).collect(),
+                comment_count: Some(issue.comments),
+                text: issue.body.as_deref().map(markdown_to_text),
+            })
+        }
+        GithubUrl::PullRequest {
+            owner,
+            repo,
+            number,
+        } => {
+            let pull = octocrab.pulls(owner, repo).get(*number).await?;
+            let state = if pull.merged_at.is_some() {
+                Some("merged")
+            } else {
+                (pull.state == Some(IssueState::Closed)).then_some("closed")
+            };
+            Ok(PageInfo {
+                title: page_title(
+                    &format!("{owner}/{repo}#{number}"),
+                    pull.title.as_deref().unwrap_or_default(),
+                    state,
+                ),
+                author: pull.user.map(|user| user.login),
+                published_at: pull.created_at,
+                tags: pull
+                    .labels
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|label| label.name)
+                    .collect(),
+                comment_count: None,
+                text: pull.body.as_deref().map(markdown_to_text),
+            })
+        }
+        GithubUrl::Release { owner, repo, tag } => {
+            let repos = octocrab.repos(owner, repo);
+            let releases = repos.releases();
+            let release = match tag {
+                Some(tag) => releases.get_by_tag(tag).await?,
+                None => releases.get_latest().await?,
+            };
+            Ok(PageInfo::from_release(owner, repo, release))
+        }
+        GithubUrl::Blob {
+            owner,
+            repo,
+            reference,
+            path,
+        } => {
+            let mut contents = octocrab
+                .repos(owner, repo)
+                .get_content()
+                .path(path)
+                .r#ref(reference)
+                .send()
+                .await?;
+            let file = contents
+                .take_items()
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("no such file: {owner}/{repo}/{path}"))?;
+            let language = language_of_path(path);
+            let is_markdown = language == Some("Markdown");
+            Ok(PageInfo {
+                title: format!("{owner}/{repo}: {path}"),
+                author: Some(owner.clone()),
+                published_at: None,
+                tags: language.into_iter().map(str::to_owned).collect(),
+                comment_count: None,
+                text: file.decoded_content().map(|content| {
+                    if is_markdown {
+                        markdown_to_text(&content)
+                    } else {
+                        content
+                    }
+                }),
+            })
+        }
+        GithubUrl::Discussion {
+            owner,
+            repo,
+            number,
+        } => fetch_discussion(octocrab, owner, repo, *number).await,
+        GithubUrl::Gist { id } => {
+            let gist = octocrab.gists().get(id).await?;
+            let mut tags: Vec<String> = vec![];
+            for language in gist.files.values().filter_map(|file| file.language.clone()) {
+                if !tags.contains(&language) {
+                    tags.push(language);
+                }
+            }
+            let text = gist
+                .files
+                .values()
+                .filter_map(|file| {
+                    let content = file.content.as_ref()?;
+                    Some(format!("{}\n\n{content}", file.filename))
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            Ok(PageInfo {
+                title: gist
+                    .description
+                    .filter(|description| !description.trim().is_empty())
+                    .or_else(|| gist.files.keys().next().cloned())
+                    .unwrap_or(format!("gist {id}")),
+                author: None,
+                published_at: Some(gist.created_at),
+                tags,
+                comment_count: Some(gist.comments as u32),
+                text: (!text.is_empty()).then_some(text),
+            })
+        }
+    }
+}
+
+/// Discussions are only in GitHub's GraphQL API.
+async fn fetch_discussion(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    number: u64,
+) -> Result<PageInfo> {
+    let query = "query($owner: String!, $repo: String!, $number: Int!) {
+        repository(owner: $owner, name: $repo) {
+            discussion(number: $number) {
+                title body createdAt closed
+                author { login }
+                category { name }
+                comments { totalCount }
+            }
+        }
+    }";
+    let response: serde_json::Value = octocrab
+        .graphql(&json!({
+            "query": query,
+            "variables": { "owner": owner, "repo": repo, "number": number },
+        }))
+        .await?;
+    let discussion = &response["data"]["repository"]["discussion"];
+    if discussion.is_null() {
+        return Err(anyhow!(
+            "no such discussion: {owner}/{repo}#{number}: {}",
+            response["errors"]
+        ));
+    }
+    Ok(PageInfo {
+        title: page_title(
+            &format!("{owner}/{repo}#{number}"),
+            discussion["title"].as_str().unwrap_or_default(),
+            (discussion["closed"] == json!(true)).then_some("closed"),
+        ),
+        author: discussion["author"]["login"].as_str().map(str::to_owned),
+        published_at: discussion["createdAt"]
+            .as_str()
+            .and_then(super::date::parse_date),
+        tags: discussion["category"]["name"]
+            .as_str()
+            .map(str::to_owned)
+            .into_iter()
+            .collect(),
+        comment_count: discussion["comments"]["totalCount"]
+            .as_u64()
+            .map(|count| count as u32),
+        text: discussion["body"].as_str().map(markdown_to_text),
+    })
+}
+
+/// Whether a path segment is the id of a gist, which is hexadecimal.
+fn is_gist_id(segment: &str) -> bool {
+    !segment.is_empty() && segment.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// `{name}: {title}`, followed by the state of the page if it's notable, e.g.
+/// `rust-lang/rust#1: Fix it (merged)`.
+fn page_title(name: &str, title: &str, state: Option<&str>) -> String {
+    let mut page_title = if title.trim().is_empty() {
+        name.to_owned()
+    } else {
+        format!("{name}: {}", title.trim())
+    };
+    if let Some(state) = state {
+        page_title.push_str(&format!(" ({state})"));
+    }
+    page_title
+}
+
+/// The language of a file, by its extension.
+fn language_of_path(path: &str) -> Option<&'static str> {
+    let file_name = path.rsplit('/').next()?;
+    let language = match file_name {
+        "Makefile" => "Makefile",
+        "Dockerfile" => "Dockerfile",
+        _ => match file_name.rsplit_once('.')?.1.to_lowercase().as_str() {
+            "rs" => "Rust",
+            "py" => "Python",
+            "js" | "mjs" | "cjs" => "JavaScript",
+            "ts" | "tsx" => "TypeScript",
+            "go" => "Go",
+            "c" | "h" => "C",
+            "cc" | "cpp" | "cxx" | "hpp" => "C++",
+            "java" => "Java",
+            "kt" => "Kotlin",
+            "swift" => "Swift",
+            "rb" => "Ruby",
+            "hs" => "Haskell",
+            "ml" | "mli" => "OCaml",
+            "scala" => "Scala",
+            "clj" => "Clojure",
+            "ex" | "exs" => "Elixir",
+            "erl" => "Erlang",
+            "lean" => "Lean",
+            "agda" => "Agda",
+            "nix" => "Nix",
+            "zig" => "Zig",
+            "lua" => "Lua",
+            "sh" | "bash" => "Shell",
+            "sql" => "SQL",
+            "html" => "HTML",
+            "css" => "CSS",
+            "md" | "markdown" => "Markdown",
+            "tex" => "TeX",
+            "toml" => "TOML",
+            "yaml" | "yml" => "YAML",
+            "json" => "JSON",
+            _ => return None,
+        },
+    };
+    Some(language)
+}
+
+/// Renders markdown, like a README, to plain text. Markup, raw HTML and
+/// images, which are mostly badges in READMEs, are dropped. Each block, like a
+/// paragraph or a list item, starts on a line of its own, and blank lines are
+/// dropped.
+pub fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut image_depth = 0;
+    for event in Parser::new_ext(markdown, Options::ENABLE_TABLES) {
+        match event {
+            Event::Start(Tag::Image { .. }) => image_depth += 1,
+            Event::End(TagEnd::Image) => image_depth -= 1,
+            _ if image_depth > 0 => (),
+            Event::Text(s) | Event::Code(s) => text.push_str(&s),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::Start(Tag::Item) => {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text.push_str("- ");
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::Item
+                | TagEnd::BlockQuote(_)
+                | TagEnd::TableRow
+                | TagEnd::TableHead,
+            ) if !text.ends_with('\n') => text.push('\n'),
+            Event::End(TagEnd::TableCell) => text.push(' '),
+            _ => (),
+        }
+    }
+
+    // collapse the blank lines left by dropped HTML and images
+    text.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(url: &str) -> Option<GithubUrl> {
+        GithubUrl::parse(url)
+    }
+
+    fn repo() -> (String, String) {
+        ("rust-lang".to_owned(), "rust".to_owned())
+    }
+
+    #[test]
+    fn parses_repo_urls() {
+        let (owner, repo) = repo();
+        let repo_url = Some(GithubUrl::Repo { owner, repo });
+        for url in [
+            "https://github.com/rust-lang/rust",
+            "https://www.github.com/rust-lang/rust/",
+            "https://github.com/rust-lang/rust.git",
+            "https://github.com/rust-lang/rust/tree/master/src",
+            // not one of the other kinds without a number
+            "https://github.com/rust-lang/rust/issues",
+            "https://github.com/rust-lang/rust/pull/new",
+        ] {
+            assert_eq!(parse(url), repo_url, "{url}");
+        }
+    }
+
+    #[test]
+    fn parses_page_urls() {
+        let (owner, repo) = repo();
+        assert_eq!(
+            parse("https://github.com/rust-lang/rust/issues/42#issuecomment-1"),
+            Some(GithubUrl::Issue {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                number: 42
+            })
+        );
+        assert_eq!(
+            parse("https://github.com/rust-lang/rust.git/pull/42/files"),
+            Some(GithubUrl::PullRequest {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                number: 42
+            })
+        );
+        assert_eq!(
+            parse("https://github.com/rust-lang/rust/discussions/42"),
+            Some(GithubUrl::Discussion {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                number: 42
+            })
+        );
+        assert_eq!(
+            parse("https://github.com/rust-lang/rust/releases/tag/release/1.0"),
+            Some(GithubUrl::Release {
+                owner: owner.clone(),
+                repo: repo.clone(),
+                tag: Some("release/1.0".to_owned())
+            })
+        );
+        for url in [
+            "https://github.com/rust-lang/rust/releases",
+            "https://github.com/rust-lang/rust/releases/latest",
+        ] {
+            assert_eq!(
+                parse(url),
+                Some(GithubUrl::Release {
+                    owner: owner.clone(),
+                    repo: repo.clone(),
+                    tag: None
+                })
+            );
+        }
+        assert_eq!(
+            parse("https://github.com/rust-lang/rust/blob/1.0/src/lib.rs"),
+            Some(GithubUrl::Blob {
+                owner,
+                repo,
+                reference: "1.0".to_owned(),
+                path: "src/lib.rs".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn parses_gist_urls() {
+        let gist = Some(GithubUrl::Gist {
+            id: "aa5a315d61ae9438b18d".to_owned(),
+        });
+        assert_eq!(parse("https://gist.github.com/aa5a315d61ae9438b18d"), gist);
+        assert_eq!(
+            parse("https://gist.github.com/octocat/aa5a315d61ae9438b18d"),
+            gist
+        );
+        assert_eq!(
+            parse("https://gist.github.com/octocat/aa5a315d61ae9438b18d/revisions"),
+            gist
+        );
+        // profiles
+        assert_eq!(parse("https://gist.github.com/octocat"), None);
+        assert_eq!(parse("https://gist.github.com/octocat/starred"), None);
+        assert_eq!(parse("https://gist.github.com/"), None);
+    }
+
+    #[test]
+    fn doesnt_parse_other_urls() {
+        for url in [
+            "https://github.com/",
+            "https://github.com/rust-lang",
+            "https://github.com/orgs/rust-lang",
+            "https://github.com/topics/rust",
+            "https://github.com/settings/profile",
+            "https://github.com/marketplace/actions/cache",
+            "https://example.com/rust-lang/rust",
+            "not a url",
+        ] {
+            assert_eq!(parse(url), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn titles_repos_with_their_descriptions() {
+        assert_eq!(
+            test_repo_info(Some(" Empowering everyone "), None).title(),
+            "rust-lang/rust: Empowering everyone"
+        );
+        assert_eq!(test_repo_info(Some("  "), None).title(), "rust-lang/rust");
+        assert_eq!(test_repo_info(None, None).title(), "rust-lang/rust");
+    }
+
+    #[test]
+    fn renders_markdown_to_text() {
+        let markdown = "\
+# Project [![CI](https://example.com/badge.svg)](https://example.com/ci)
+
+A *fast* tool,
+written in `Rust`.
+
+<div align=\"center\">raw html</div>
+
+- one
+- two
+  three
+
+```sh
+cargo install project
+```
+
+| a | b |
+|---|---|
+| 1 | 2 |
+";
+        assert_eq!(
+            markdown_to_text(markdown),
+            "\
+Project
+A fast tool, written in Rust.
+- one
+- two three
+cargo install project
+a b
+1 2"
+        );
+    }
+}
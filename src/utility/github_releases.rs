@@ -0,0 +1,359 @@
+//! Watching GitHub repositories for new releases and tags, for the
+//! `github_releases` feed source.
+//!
+//! How far each repository has been read is kept as a [`RepoWatermark`], and
+//! the watermarks of all of a feed's repositories are stored together, in
+//! JSON, as the feed's watermark (see [`crate::utility::db::get_feed_watermark`]).
+//! The first time a repository is read, its watermark is only recorded, so
+//! that its old releases aren't published as new ones.
+use crate::{
+    embellisher::{self, ExtractedContent},
+    models::Preview,
+    utility::github::PageInfo,
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use octocrab::Octocrab;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// The newest release and the tags of a repository that have been seen.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoWatermark {
+    /// When the newest release was published.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<DateTime<Utc>>,
+    /// The names of the tags that were listed last time, if tags were. Tags
+    /// are listed by name rather than by when they were made, so a tag is new
+    /// if it wasn't listed before, wherever it is listed, like a tag of a
+    /// backported fix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<BTreeSet<String>>,
+}
+
+/// The watermarks of repositories, by `owner/name`.
+pub type Watermarks = BTreeMap<String, RepoWatermark>;
+
+/// Parses a feed's stored watermark. A watermark that can't be parsed is
+/// treated as if the feed hadn't been run before.
+pub fn parse_watermarks(watermark: Option<&str>) -> Watermarks {
+    watermark
+        .and_then(|watermark| match serde_json::from_str(watermark) {
+            Ok(watermarks) => Some(watermarks),
+            Err(e) => {
+                log::warn!["ignoring invalid GitHub releases watermark: {e}"];
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// The `owner/name` of every repository that the authenticated user has
+/// starred.
+pub async fn fetch_starred_repos(octocrab: &Octocrab) -> Result<Vec<String>> {
+    let page = octocrab
+        .current()
+        .list_repos_starred_by_authenticated_user()
+        .per_page(100)
+        .send()
+        .await?;
+    Ok(octocrab
+        .all_pages(page)
+        .await?
+        .into_iter()
+        .filter_map(|repo| repo.full_name)
+        .collect())
+}
+
+/// Fetches the releases of the repository that were published since its
+/// watermark, and, with `include_tags`, the tags that aren't releases and
+/// weren't seen before, at most `limit` of each, newest first. Returns them as
+/// embellished previews, along with the repository's new watermark. Without a
+/// watermark, or without seen tags, nothing is returned but the watermark.
+pub async fn fetch_new_releases(
+    octocrab: &Octocrab,
+    full_name: &str,
+    watermark: Option<&RepoWatermark>,
+    include_tags: bool,
+    limit: usize,
+) -> Result<(Vec<(Preview, Option<ExtractedContent>)>, RepoWatermark)> {
+    let (owner, repo) = full_name
+        .split_once('/')
+        .ok_or(anyhow!("not a GitHub repository: {full_name}"))?;
+    let per_page = limit.clamp(1, 100) as u8;
+    let mut previews = vec![];
+    let mut new_watermark = watermark.cloned().unwrap_or_default();
+
+    let releases = octocrab
+        .repos(owner, repo)
+        .releases()
+        .list()
+        .per_page(per_page)
+        .send()
+        .await?
+        .take_items();
+    let release_tags: HashSet<String> = releases
+        .iter()
+        .map(|release| release.tag_name.clone())
+        .collect();
+    let mut new_releases = 0;
+    for release in releases.into_iter().filter(|release| !release.draft) {
+        let Some(published_at) = release.published_at else {
+            continue;
+        };
+        new_watermark.release = new_watermark.release.max(Some(published_at));
+        let Some(watermark) = watermark else {
+            continue;
+        };
+        if watermark
+            .release
+            .is_some_and(|last_published| published_at <= last_published)
+            || new_releases >= limit
+        {
+            continue;
+        }
+        new_releases += 1;
+
+        let mut preview = Preview::from_url(release.html_url.to_string());
+        let content = embellisher::github::apply_page_info(
+            &mut preview,
+            PageInfo::from_release(owner, repo, release),
+        );
+        preview.embellished = true;
+        previews.push((preview, content));
+    }
+
+    if include_tags {
+        let tags = match octocrab
+            .repos(owner, repo)
+            .list_tags()
+            .per_page(per_page)
+            .send()
+            .await
+        {
+            Ok(mut tags) => tags.take_items(),
+            Err(e) => {
+                log::warn!["failed to fetch the tags of {full_name}: {e}"];
+                vec![]
+            }
+        };
+        let seen_tags = watermark.and_then(|watermark| watermark.tags.clone());
+        new_watermark.tags = Some(tags.iter().map(|tag| tag.name.clone()).collect());
+        for tag in tags
+            .iter()
+            .filter(|tag| {
+                seen_tags
+                    .as_ref()
+                    .is_some_and(|seen_tags| !seen_tags.contains(&tag.name))
+            })
+            .filter(|tag| !release_tags.contains(&tag.name))
+            .take(limit)
+        {
+            let mut preview = Preview::from_url(format!(
+                "https://github.com/{full_name}/releases/tag/{}",
+                tag.name
+            ));
+            preview.title = Some(format!("{full_name} {}", tag.name));
+            preview.site_name = Some("GitHub".to_owned());
+            preview.author = Some(owner.to_owned());
+            preview.embellished = true;
+            previews.push((preview, None));
+        }
+    }
+
+    Ok((previews, new_watermark))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::mock_server::mock_server;
+
+    const RELEASES: &str = r#"[
+        {
+            "url": "https://api.github.com/repos/o/r/releases/2",
+            "html_url": "https://github.com/o/r/releases/tag/v2.0.0",
+            "assets_url": "https://api.github.com/repos/o/r/releases/2/assets",
+            "upload_url": "https://uploads.github.com/repos/o/r/releases/2/assets",
+            "tarball_url": null,
+            "zipball_url": null,
+            "id": 2,
+            "node_id": "R2",
+            "tag_name": "v2.0.0",
+            "target_commitish": "main",
+            "name": "Two",
+            "body": "The **second** release.",
+            "draft": false,
+            "prerelease": false,
+            "created_at": "2026-10-10T00:00:00Z",
+            "published_at": "2026-10-10T00:00:00Z",
+            "author": null,
+            "assets": []
+        },
+        {
+            "url": "https://api.github.com/repos/o/r/releases/1",
+            "html_url": "https://github.com/o/r/releases/tag/v1.0.0",
+            "assets_url": "https://api.github.com/repos/o/r/releases/1/assets",
+            "upload_url": "https://uploads.github.com/repos/o/r/releases/1/assets",
+            "tarball_url": null,
+            "zipball_url": null,
+            "id": 1,
+            "node_id": "R1",
+            "tag_name": "v1.0.0",
+            "target_commitish": "main",
+            "name": "One",
+            "body": null,
+            "draft": false,
+            "prerelease": false,
+            "created_at": "2026-10-01T00:00:00Z",
+            "published_at": "2026-10-01T00:00:00Z",
+            "author": null,
+            "assets": []
+        }
+    ]"#;
+
+    /// Tags are listed by name, so the tag of a backported fix, `v1.0.1`, is
+    /// listed after newer ones.
+    const TAGS: &str = r#"[
+        {
+            "name": "v2.0.0",
+            "commit": {"sha": "c2", "url": "https://api.github.com/repos/o/r/commits/c2"},
+            "zipball_url": "https://api.github.com/repos/o/r/zipball/v2.0.0",
+            "tarball_url": "https://api.github.com/repos/o/r/tarball/v2.0.0",
+            "node_id": "T2"
+        },
+        {
+            "name": "v1.0.1",
+            "commit": {"sha": "c11", "url": "https://api.github.com/repos/o/r/commits/c11"},
+            "zipball_url": "https://api.github.com/repos/o/r/zipball/v1.0.1",
+            "tarball_url": "https://api.github.com/repos/o/r/tarball/v1.0.1",
+            "node_id": "T11"
+        },
+        {
+            "name": "v1.0.0",
+            "commit": {"sha": "c1", "url": "https://api.github.com/repos/o/r/commits/c1"},
+            "zipball_url": "https://api.github.com/repos/o/r/zipball/v1.0.0",
+            "tarball_url": "https://api.github.com/repos/o/r/tarball/v1.0.0",
+            "node_id": "T1"
+        }
+    ]"#;
+
+    /// A client of a mock of the API, like one built with `GITHUB_API_URL`.
+    async fn octocrab() -> Octocrab {
+        let api_url = mock_server(vec![
+            ("/repos/o/r/releases", RELEASES),
+            ("/repos/o/r/tags", TAGS),
+        ])
+        .await;
+        Octocrab::builder()
+            .base_uri(api_url)
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn urls(previews: &[(Preview, Option<ExtractedContent>)]) -> Vec<&str> {
+        previews
+            .iter()
+            .map(|(preview, _)| preview.url.as_str())
+            .collect()
+    }
+
+    fn time(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().to_utc()
+    }
+
+    #[tokio::test]
+    async fn only_records_the_watermark_on_the_first_run() {
+        let (previews, watermark) = fetch_new_releases(&octocrab().await, "o/r", None, true, 10)
+            .await
+            .unwrap();
+        assert!(previews.is_empty());
+        assert_eq!(watermark.release, Some(time("2026-10-10T00:00:00Z")));
+        assert_eq!(
+            watermark.tags.unwrap().into_iter().collect::<Vec<_>>(),
+            ["v1.0.0", "v1.0.1", "v2.0.0"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_the_releases_since_the_watermark() {
+        let watermark = RepoWatermark {
+            release: Some(time("2026-10-05T00:00:00Z")),
+            tags: None,
+        };
+        let (previews, new_watermark) =
+            fetch_new_releases(&octocrab().await, "o/r", Some(&watermark), false, 10)
+                .await
+                .unwrap();
+        assert_eq!(
+            urls(&previews),
+            ["https://github.com/o/r/releases/tag/v2.0.0"]
+        );
+        let (preview, content) = &previews[0];
+        assert_eq!(preview.title.as_deref(), Some("o/r v2.0.0: Two"));
+        assert!(preview.embellished);
+        assert_eq!(content.as_ref().unwrap().text, "The second release.");
+        assert_eq!(new_watermark.release, Some(time("2026-10-10T00:00:00Z")));
+        assert_eq!(new_watermark.tags, None);
+    }
+
+    #[tokio::test]
+    async fn fetches_the_tags_that_werent_seen() {
+        let watermark = RepoWatermark {
+            release: Some(time("2026-10-10T00:00:00Z")),
+            tags: Some(BTreeSet::from(["v1.0.0".to_owned(), "v2.0.0".to_owned()])),
+        };
+        let (previews, new_watermark) =
+            fetch_new_releases(&octocrab().await, "o/r", Some(&watermark), true, 10)
+                .await
+                .unwrap();
+        // the backported tag, although it isn't the newest
+        assert_eq!(
+            urls(&previews),
+            ["https://github.com/o/r/releases/tag/v1.0.1"]
+        );
+        assert!(new_watermark.tags.unwrap().contains("v1.0.1"));
+
+        // tags that are releases are only fetched as releases, and tags that
+        // were deleted since don't make the others look new
+        let watermark = RepoWatermark {
+            release: Some(time("2026-10-01T00:00:00Z")),
+            tags: Some(BTreeSet::from(["v0.9.0".to_owned(), "v1.0.1".to_owned()])),
+        };
+        let (previews, _) =
+            fetch_new_releases(&octocrab().await, "o/r", Some(&watermark), true, 10)
+                .await
+                .unwrap();
+        assert_eq!(
+            urls(&previews),
+            ["https://github.com/o/r/releases/tag/v2.0.0"]
+        );
+    }
+
+    #[test]
+    fn parses_watermarks() {
+        assert_eq!(parse_watermarks(None), Watermarks::new());
+        assert_eq!(parse_watermarks(Some("not JSON")), Watermarks::new());
+
+        let watermarks = parse_watermarks(Some(
+            r#"{"o/r": {"release": "2026-10-10T00:00:00Z", "tags": ["v1"]}, "o/s": {}}"#,
+        ));
+        assert_eq!(
+            watermarks["o/r"],
+            RepoWatermark {
+                release: Some(time("2026-10-10T00:00:00Z")),
+                tags: Some(BTreeSet::from(["v1".to_owned()])),
+            }
+        );
+        assert_eq!(watermarks["o/s"], RepoWatermark::default());
+        assert_eq!(
+            serde_json::to_string(&watermarks).unwrap(),
+            r#"{"o/r":{"release":"2026-10-10T00:00:00Z","tags":["v1"]},"o/s":{}}"#
+        );
+
+        // the newest tag, as it was stored before, is seen as no tags
+        let watermarks = parse_watermarks(Some(r#"{"o/r": {"tag": "v1"}}"#));
+        assert_eq!(watermarks["o/r"].tags, None);
+    }
+}
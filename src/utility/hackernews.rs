@@ -0,0 +1,231 @@
+//! Fetching stories and their discussions from the
+//! [Hacker News API](https://github.com/HackerNews/API).
+//!
+//! The API is served from [`DEFAULT_API_URL`] unless the `HACKERNEWS_API_URL`
+//! environment variable says otherwise (see [`api_url`]).
+use crate::{config, models::Preview, utility::http::Fetcher};
+use anyhow::Result;
+use serde::Deserialize;
+
+pub const DEFAULT_API_URL: &str = "https://hacker-news.firebaseio.com/v0";
+
+/// The URL of the discussion of an item is this followed by its id.
+const DISCUSSION_URL_PREFIX: &str = "https://news.ycombinator.com/item?id=";
+
+/// An item of the API, which may be a story, comment, job or poll.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Item {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
+    /// The username of the submitter.
+    pub by: Option<String>,
+    /// When the item was submitted, in Unix time.
+    pub time: Option<i64>,
+    pub title: Option<String>,
+    /// The URL that a story links to. Text posts, like Ask HN, don't have one.
+    pub url: Option<String>,
+    /// The HTML text of a text post or comment.
+    pub text: Option<String>,
+    pub score: Option<i64>,
+    /// The number of comments in the discussion.
+    pub descendants: Option<i64>,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub dead: bool,
+}
+
+impl Item {
+    pub fn discussion_url(&self) -> String {
+        discussion_url(self.id)
+    }
+
+    /// Records the discussion of the item on the preview, and fills in the
+    /// title and published date if the preview doesn't have them yet.
+    pub fn apply_to(&self, preview: &mut Preview) {
+        preview.score = self.score.map(|score| score as i32);
+        preview.comment_count = self.descendants.map(|count| count as i32);
+        preview.submitter = self.by.clone();
+        preview.discussion_url = Some(self.discussion_url());
+        if preview.title.is_none() {
+            preview.title = self.title.clone();
+        }
+        if preview.published_date().is_none()
+            && let Some(time) = self
+                .time
+                .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+        {
+            preview.set_published_date(Some(time.to_rfc3339()));
+        }
+    }
+
+    /// The plain text of a text post.
+    pub fn plain_text(&self) -> Option<String> {
+        let document = scraper::Html::parse_fragment(self.text.as_deref()?);
+        let text = document.root_element().text().collect::<Vec<_>>().join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+/// The base URL of the API.
+pub fn api_url() -> &'static str {
+    config::HACKERNEWS_API_URL
+        .as_deref()
+        .unwrap_or(DEFAULT_API_URL)
+}
+
+pub fn discussion_url(id: u64) -> String {
+    format!("{DISCUSSION_URL_PREFIX}{id}")
+}
+
+/// The id of the item that a discussion URL is for, e.g. `42` for
+/// `https://news.ycombinator.com/item?id=42`.
+pub fn get_item_id_from_url(url: &str) -> Option<u64> {
+    let url = url::Url::parse(url).ok()?;
+    if !matches!(
+        url.host_str()?,
+        "news.ycombinator.com" | "www.ycombinator.com"
+    ) || url.path() != "/item"
+    {
+        return None;
+    }
+    url.query_pairs()
+        .find(|(name, _)| name == "id")
+        .and_then(|(_, id)| id.parse().ok())
+}
+
+/// Fetches the item with the id, if there is one.
+pub async fn fetch_item(fetcher: &Fetcher, api_url: &str, id: u64) -> Result<Option<Item>> {
+    let response = fetcher.get(&format!("{api_url}/item/{id}.json")).await?;
+    // the API responds with `null` for items that don't exist
+    response.json::<Option<Item>>()
+}
+
+/// Fetches the ids of the current best stories, best first.
+pub async fn fetch_best_stories(fetcher: &Fetcher, api_url: &str) -> Result<Vec<u64>> {
+    let response = fetcher.get(&format!("{api_url}/beststories.json")).await?;
+    response.json::<Vec<u64>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::mock_server::mock_server;
+
+    const STORY: &str = r#"{
+        "by": "dhouston",
+        "descendants": 71,
+        "id": 8863,
+        "kids": [8952, 9224],
+        "score": 111,
+        "time": 1175714200,
+        "title": "My YC app: Dropbox - Throw away your USB drive",
+        "type": "story",
+        "url": "http://www.getdropbox.com/u/2/screencast.html"
+    }"#;
+
+    const ASK: &str = r#"{
+        "by": "tel",
+        "descendants": 16,
+        "id": 121003,
+        "score": 25,
+        "text": "<i>or</i> HN: the Next Iteration<p>I get the impression that with Arc being released a lot of people who never had time for HN before are suddenly dropping in more often.",
+        "time": 1203647620,
+        "title": "Ask HN: The Arc Effect",
+        "type": "story"
+    }"#;
+
+    #[tokio::test]
+    async fn fetches_items() {
+        let base_url = mock_server(vec![
+            ("/v0/item/8863.json", STORY),
+            ("/v0/item/121003.json", ASK),
+            ("/v0/item/1.json", "null"),
+        ])
+        .await;
+        let api_url = format!("{base_url}/v0");
+        let fetcher = Fetcher::new().unwrap();
+
+        let story = fetch_item(&fetcher, &api_url, 8863).await.unwrap().unwrap();
+        assert_eq!(story.by.as_deref(), Some("dhouston"));
+        assert_eq!(story.score, Some(111));
+        assert_eq!(story.descendants, Some(71));
+        assert_eq!(
+            story.url.as_deref(),
+            Some("http://www.getdropbox.com/u/2/screencast.html")
+        );
+
+        let ask = fetch_item(&fetcher, &api_url, 121003)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ask.url, None);
+        assert!(
+            ask.plain_text()
+                .unwrap()
+                .starts_with("or HN: the Next Iteration I get")
+        );
+
+        assert!(fetch_item(&fetcher, &api_url, 1).await.unwrap().is_none());
+        assert!(fetch_item(&fetcher, &api_url, 2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn fetches_best_stories() {
+        let base_url = mock_server(vec![("/v0/beststories.json", "[8863, 121003]")]).await;
+        let api_url = format!("{base_url}/v0");
+        let fetcher = Fetcher::new().unwrap();
+
+        let ids = fetch_best_stories(&fetcher, &api_url).await.unwrap();
+        assert_eq!(ids, vec![8863, 121003]);
+    }
+
+    #[test]
+    fn records_discussion_on_preview() {
+        let story: Item = serde_json::from_str(STORY).unwrap();
+        let mut preview = Preview::from_url(story.url.clone().unwrap());
+        story.apply_to(&mut preview);
+
+        assert_eq!(preview.score, Some(111));
+        assert_eq!(preview.comment_count, Some(71));
+        assert_eq!(preview.submitter.as_deref(), Some("dhouston"));
+        assert_eq!(
+            preview.discussion_url.as_deref(),
+            Some("https://news.ycombinator.com/item?id=8863")
+        );
+        assert_eq!(
+            preview.title.as_deref(),
+            Some("My YC app: Dropbox - Throw away your USB drive")
+        );
+        assert_eq!(
+            preview.published_at.map(|time| time.to_string()).as_deref(),
+            Some("2007-04-04 19:16:40")
+        );
+    }
+
+    #[test]
+    fn gets_item_ids_from_urls() {
+        assert_eq!(
+            get_item_id_from_url("https://news.ycombinator.com/item?id=8863"),
+            Some(8863)
+        );
+        assert_eq!(
+            get_item_id_from_url("https://news.ycombinator.com/item?id=8863&p=2"),
+            Some(8863)
+        );
+        assert_eq!(
+            get_item_id_from_url("https://news.ycombinator.com/news"),
+            None
+        );
+        assert_eq!(
+            get_item_id_from_url("https://example.com/item?id=8863"),
+            None
+        );
+        assert_eq!(
+            get_item_id_from_url("https://news.ycombinator.com/item?id=abc"),
+            None
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! The HTTP client that every request goes through.
+//!
+//! A [`Fetcher`] identifies itself with [`USER_AGENT`], times out on slow
+//! connections and reads, retries with exponential backoff on 429 and 5xx
+//! responses (honoring their `Retry-After`), on connection errors and, for
+//! idempotent requests, on timeouts, and refuses to read bodies larger than
+//! its maximum, so that one slow or huge response can't hold up a whole run.
+//!
+//! With a cache directory, the responses to GET requests are cached, and
+//! requested again conditionally (see [`HttpCache`]). In offline mode, GET
+//! requests are only answered from the cache, and no other request is sent, so
+//! that a run can be replayed.
+use crate::{config, utility::http_cache::HttpCache};
+use anyhow::{Result, anyhow};
+use reqwest::{
+    Method, StatusCode,
+    header::{ETAG, HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use std::time::Duration;
+use thiserror::Error;
+
+pub const USER_AGENT: &str = concat!(
+    "linkstitcher/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/rybla/linkstitcher)"
+);
+
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+pub const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+pub const DEFAULT_MAX_BODY_BYTES: usize = 20 * 1024 * 1024;
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+pub const DEFAULT_MAX_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum FetchError {
+    #[error("{url} responded with {status}")]
+    Status { url: String, status: StatusCode },
+    #[error("the body of {url} is larger than {max_body_bytes} bytes")]
+    TooLarge { url: String, max_body_bytes: usize },
+    #[error("{url} isn't cached, so it can't be fetched offline")]
+    NotCached { url: String },
+    #[error("{url} can't be requested offline")]
+    Offline { url: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct FetcherOptions {
+    pub connect_timeout: Duration,
+    /// How long to wait for each read of a response.
+    pub read_timeout: Duration,
+    /// How long to wait for a whole request, including its redirects.
+    pub timeout: Duration,
+    /// How many times a failed request is retried.
+    pub max_retries: u32,
+    /// How long to wait before the first retry, which doubles for each retry.
+    pub base_delay: Duration,
+    /// The longest to wait before a retry. A response whose `Retry-After` asks
+    /// for longer isn't retried.
+    pub max_delay: Duration,
+    pub max_body_bytes: usize,
+    pub max_redirects: usize,
+    /// Where the responses to GET requests are cached, if anywhere.
+    pub cache_dirpath: Option<String>,
+    /// How large the cache may grow before its least recently fetched
+    /// responses are evicted, which happens when the fetcher is built.
+    pub max_cache_bytes: u64,
+    /// Whether GET requests are only answered from the cache, and no other
+    /// request is sent.
+    pub offline: bool,
+}
+
+impl Default for FetcherOptions {
+    fn default() -> Self {
+        FetcherOptions {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            timeout: DEFAULT_TIMEOUT,
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cache_dirpath: None,
+            max_cache_bytes: DEFAULT_MAX_CACHE_BYTES,
+            offline: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    client: reqwest::Client,
+    cache: Option<HttpCache>,
+    options: FetcherOptions,
+}
+
+/// A successful response, with its whole body.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// The URL of the response, after any redirects.
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    /// Whether the server responded with 304 Not Modified, so this is the
+    /// cached response.
+    pub not_modified: bool,
+}
+
+impl Response {
+    /// The value of the `Content-Type` header, if there is one.
+    pub fn content_type(&self) -> Option<String> {
+        self.headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+    }
+
+    /// The body decoded as text, in the charset that the `Content-Type`
+    /// header declares, or else in UTF-8. A byte order mark takes precedence,
+    /// like in browsers.
+    pub fn text(&self) -> String {
+        let encoding = self
+            .content_type()
+            .and_then(|content_type| content_type.parse::<mime::Mime>().ok())
+            .and_then(|mime| {
+                let charset = mime.get_param(mime::CHARSET)?;
+                encoding_rs::Encoding::for_label(charset.as_str().as_bytes())
+            })
+            .unwrap_or(encoding_rs::UTF_8);
+        let (text, _, _) = encoding.decode(&self.body);
+        text.into_owned()
+    }
+
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+impl Fetcher {
+    pub fn new() -> Result<Self> {
+        Self::with_options(FetcherOptions::default())
+    }
+
+    pub fn with_options(options: FetcherOptions) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .connect_timeout(options.connect_timeout)
+            .read_timeout(options.read_timeout)
+            .timeout(options.timeout)
+            .redirect(reqwest::redirect::Policy::limited(options.max_redirects))
+            .build()?;
+        let cache = match &options.cache_dirpath {
+            None => None,
+            Some(dirpath) => {
+                let cache = HttpCache::new(dirpath)?;
+                match cache.evict(options.max_cache_bytes) {
+                    Ok(0) => {}
+                    Ok(count) => log::info!["fetcher: evicted {count} cached responses"],
+                    Err(e) => log::warn!["failed to evict cached responses: {e}"],
+                }
+                Some(cache)
+            }
+        };
+        Ok(Fetcher {
+            client,
+            cache,
+            options,
+        })
+    }
+
+    pub fn options(&self) -> &FetcherOptions {
+        &self.options
+    }
+
+    /// The underlying client, for building requests to [`Fetcher::send`].
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Sends a GET request, conditionally if the response is cached, and
+    /// caches the response.
+    pub async fn get(&self, url: &str) -> Result<Response> {
+        let cached = match &self.cache {
+            None => None,
+            Some(cache) => cache.load(url).unwrap_or_else(|e| {
+                log::warn!["failed to load the cached response for {url}: {e}"];
+                None
+            }),
+        };
+
+        if self.options.offline {
+            return cached.ok_or(
+                FetchError::NotCached {
+                    url: url.to_owned(),
+                }
+                .into(),
+            );
+        }
+
+        let mut request = self.client.get(url);
+        if let Some(cached) = &cached {
+            if let Some(etag) = cached.headers.get(ETAG) {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = cached.headers.get(LAST_MODIFIED) {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = self.send(request).await?;
+        if response.status == StatusCode::NOT_MODIFIED {
+            log::debug!["fetcher: not modified: {url}"];
+            let mut cached = cached.ok_or(FetchError::Status {
+                url: url.to_owned(),
+                status: response.status,
+            })?;
+            if let Some(cache) = &self.cache
+                && let Err(e) = cache.touch(url)
+            {
+                log::warn!["failed to touch the cached response for {url}: {e}"];
+            }
+            cached.not_modified = true;
+            return Ok(cached);
+        }
+
+        if let Some(cache) = &self.cache
+            && let Err(e) = cache.store(url, &response)
+        {
+            log::warn!["failed to cache the response for {url}: {e}"];
+        }
+        Ok(response)
+    }
+
+    /// Sends the request, retrying it if it fails in a way that might not
+    /// happen again. A request that timed out is only retried if it is
+    /// idempotent, since the server may have acted on it, e.g. an LLM may still
+    /// be completing a POST. Responds with an error unless the response is
+    /// successful, or 304 Not Modified to a GET request. The response isn't
+    /// cached, and in offline mode the request isn't sent at all.
+    pub async fn send(&self, request: reqwest::RequestBuilder) -> Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        if self.options.offline {
+            let url = request.url().to_string();
+            return Err(FetchError::Offline { url }.into());
+        }
+        let is_idempotent = request.method().is_idempotent();
+        let is_get = request.method() == Method::GET;
+        let mut attempt = 0;
+        loop {
+            let retry = request.try_clone().ok_or(anyhow!(
+                "the request can't be retried, since its body is a stream"
+            ))?;
+
+            let delay = match client.execute(retry).await {
+                Ok(response) => {
+                    let status = response.status();
+                    if status.is_success() || is_get && status == StatusCode::NOT_MODIFIED {
+                        return self.read(response).await;
+                    }
+                    let url = response.url().to_string();
+                    let error = FetchError::Status { url, status };
+                    if !is_retryable(status) || attempt >= self.options.max_retries {
+                        return Err(error.into());
+                    }
+                    match retry_after(response.headers()) {
+                        Some(delay) if delay > self.options.max_delay => {
+                            return Err(error.into());
+                        }
+                        Some(delay) => delay,
+                        None => self.backoff(attempt),
+                    }
+                }
+                Err(e) => {
+                    let is_retryable = e.is_connect() || e.is_timeout() && is_idempotent;
+                    if !is_retryable || attempt >= self.options.max_retries {
+                        return Err(e.into());
+                    }
+                    log::debug!["fetcher: {e}"];
+                    self.backoff(attempt)
+                }
+            };
+
+            attempt += 1;
+            log::info![
+                "fetcher: retrying in {}ms (attempt {attempt} of {})",
+                delay.as_millis(),
+                self.options.max_retries
+            ];
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Reads the body of the response, unless it is too large.
+    async fn read(&self, mut response: reqwest::Response) -> Result<Response> {
+        let url = response.url().to_string();
+        let too_large = || FetchError::TooLarge {
+            url: url.clone(),
+            max_body_bytes: self.options.max_body_bytes,
+        };
+
+        if let Some(length) = response.content_length()
+            && length > self.options.max_body_bytes as u64
+        {
+            return Err(too_large().into());
+        }
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let mut body = vec![];
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.options.max_body_bytes {
+                return Err(too_large().into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(Response {
+            url,
+            status,
+            headers,
+            body,
+            not_modified: false,
+        })
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.options
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.options.max_delay)
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// How long the `Retry-After` header asks to wait, given either in seconds or
+/// as an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let delay = date.signed_duration_since(chrono::Utc::now());
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Builds the GitHub client with the same timeouts and User-Agent as a
+/// [`Fetcher`]. The `GITHUB_API_URL` environment variable overrides the URL of
+/// the API.
+pub fn build_octocrab(options: &FetcherOptions) -> Result<octocrab::Octocrab> {
+    let mut builder = octocrab::Octocrab::builder()
+        .personal_token(config::GITHUB_PERSONAL_ACCESS_TOKEN.as_str())
+        .set_connect_timeout(Some(options.connect_timeout))
+        .set_read_timeout(Some(options.read_timeout))
+        .add_header(reqwest::header::USER_AGENT, USER_AGENT.to_owned());
+    if let Some(api_url) = config::GITHUB_API_URL.as_deref() {
+        builder = builder.base_uri(api_url)?;
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn response(content_type: Option<&str>, body: &[u8]) -> Response {
+        let mut headers = HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(reqwest::header::CONTENT_TYPE, content_type.parse().unwrap());
+        }
+        Response {
+            url: "https://example.com/".to_owned(),
+            status: StatusCode::OK,
+            headers,
+            body: body.to_vec(),
+            not_modified: false,
+        }
+    }
+
+    #[test]
+    fn decodes_text_in_the_declared_charset() {
+        let latin1 = b"caf\xe9";
+        assert_eq!(
+            response(Some("text/html; charset=ISO-8859-1"), latin1).text(),
+            "café"
+        );
+        assert_eq!(
+            response(Some("text/plain; charset=\"windows-1252\""), b"\x93hi\x94").text(),
+            "\u{201c}hi\u{201d}"
+        );
+        assert_eq!(
+            response(Some("text/html"), "café".as_bytes()).text(),
+            "café"
+        );
+        assert_eq!(response(None, "café".as_bytes()).text(), "café");
+        // an unknown charset is read as UTF-8
+        assert_eq!(
+            response(Some("text/html; charset=bogus"), "café".as_bytes()).text(),
+            "café"
+        );
+        // a byte order mark takes precedence
+        assert_eq!(
+            response(
+                Some("text/html; charset=ISO-8859-1"),
+                b"\xef\xbb\xbfcaf\xc3\xa9"
+            )
+            .text(),
+            "café"
+        );
+    }
+
+    /// A server that accepts connections but never responds. Returns its URL
+    /// and how many connections it accepted.
+    async fn unresponsive_server() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let count = Arc::new(AtomicUsize::new(0));
+        let accepted = count.clone();
+        tokio::spawn(async move {
+            let mut streams = vec![];
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::SeqCst);
+                streams.push(stream);
+            }
+        });
+        (url, count)
+    }
+
+    #[tokio::test]
+    async fn only_retries_idempotent_requests_on_timeouts() {
+        let fetcher = Fetcher::with_options(FetcherOptions {
+            timeout: Duration::from_millis(100),
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let (url, count) = unresponsive_server().await;
+        assert!(fetcher.send(fetcher.client().get(&url)).await.is_err());
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        let (url, count) = unresponsive_server().await;
+        let request = fetcher.client().post(&url).body("{}");
+        assert!(fetcher.send(request).await.is_err());
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    /// A server that responds 304 Not Modified to every request.
+    async fn not_modified_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let _ = stream.read(&mut request).await.unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 304 Not Modified\r\nconnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn only_accepts_not_modified_for_get_requests() {
+        let fetcher = Fetcher::new().unwrap();
+        let url = not_modified_server().await;
+
+        let response = fetcher.send(fetcher.client().get(&url)).await.unwrap();
+        assert_eq!(response.status, StatusCode::NOT_MODIFIED);
+        assert!(fetcher.send(fetcher.client().post(&url)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_nothing_offline() {
+        let fetcher = Fetcher::with_options(FetcherOptions {
+            offline: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let (url, count) = unresponsive_server().await;
+
+        let error = fetcher.get(&url).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(FetchError::NotCached { .. })
+        ));
+        let error = fetcher.send(fetcher.client().post(&url)).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(FetchError::Offline { .. })
+        ));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,275 @@
+//! An on-disk cache of the responses to GET requests.
+//!
+//! Each response is stored in the cache directory as `<key>.json`, holding its
+//! URL, status and headers, next to `<key>.body`, holding its body, where the
+//! key is a hash of the requested URL. The `ETag` and `Last-Modified` headers of
+//! a cached response are sent with the next request for the same URL, so that
+//! the server can respond with 304 Not Modified instead of the whole body.
+//!
+//! The cache is bounded by evicting the least recently fetched responses, as
+//! told by the modification times of their `.json` files, which are touched
+//! whenever the server responds that a cached response is still fresh.
+use crate::utility::http::Response;
+use anyhow::Result;
+use reqwest::{
+    StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dirpath: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedMeta {
+    /// The requested URL.
+    request_url: String,
+    /// The URL of the response, after any redirects.
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl HttpCache {
+    pub fn new(dirpath: impl AsRef<Path>) -> Result<Self> {
+        let dirpath = dirpath.as_ref().to_path_buf();
+        fs::create_dir_all(&dirpath)?;
+        Ok(HttpCache { dirpath })
+    }
+
+    /// Loads the cached response to a GET request for `url`, if there is one.
+    pub fn load(&self, url: &str) -> Result<Option<Response>> {
+        let (meta_path, body_path) = self.paths(url);
+        if !meta_path.exists() || !body_path.exists() {
+            return Ok(None);
+        }
+
+        let meta: CachedMeta = serde_json::from_slice(&fs::read(meta_path)?)?;
+        if meta.request_url != url {
+            // a collision of the hashes of two URLs
+            return Ok(None);
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in meta.headers {
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(&value)?,
+            );
+        }
+
+        Ok(Some(Response {
+            url: meta.url,
+            status: StatusCode::from_u16(meta.status)?,
+            headers,
+            body: fs::read(body_path)?,
+            not_modified: false,
+        }))
+    }
+
+    /// Stores the response to a GET request for `url`, replacing any that is
+    /// already cached.
+    pub fn store(&self, url: &str, response: &Response) -> Result<()> {
+        let meta = CachedMeta {
+            request_url: url.to_owned(),
+            url: response.url.clone(),
+            status: response.status.as_u16(),
+            headers: response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.to_string(), value.to_str().ok()?.to_owned()))
+                })
+                .collect(),
+            fetched_at: chrono::Utc::now(),
+        };
+
+        let (meta_path, body_path) = self.paths(url);
+        // the body is written first, so that the metadata never refers to a
+        // body that isn't there yet
+        write_atomically(&body_path, &response.body)?;
+        write_atomically(&meta_path, &serde_json::to_vec_pretty(&meta)?)?;
+        Ok(())
+    }
+
+    /// Marks the cached response to a GET request for `url` as just fetched,
+    /// so that it is evicted last.
+    pub fn touch(&self, url: &str) -> Result<()> {
+        let (meta_path, _) = self.paths(url);
+        fs::File::options()
+            .write(true)
+            .open(meta_path)?
+            .set_modified(SystemTime::now())?;
+        Ok(())
+    }
+
+    /// Evicts the least recently fetched responses until the cache takes up
+    /// at most `max_bytes`. Returns how many were evicted.
+    pub fn evict(&self, max_bytes: u64) -> Result<usize> {
+        struct Entry {
+            meta_path: PathBuf,
+            body_path: PathBuf,
+            bytes: u64,
+            fetched_at: SystemTime,
+        }
+
+        let mut entries = vec![];
+        let mut total_bytes = 0;
+        for dir_entry in fs::read_dir(&self.dirpath)? {
+            let meta_path = dir_entry?.path();
+            if meta_path
+                .extension()
+                .is_none_or(|extension| extension != "json")
+            {
+                continue;
+            }
+            let body_path = meta_path.with_extension("body");
+            let metadata = fs::metadata(&meta_path)?;
+            let bytes = metadata.len() + fs::metadata(&body_path).map_or(0, |body| body.len());
+            total_bytes += bytes;
+            entries.push(Entry {
+                meta_path,
+                body_path,
+                bytes,
+                fetched_at: metadata.modified()?,
+            });
+        }
+
+        entries.sort_by_key(|entry| entry.fetched_at);
+        let mut count = 0;
+        for entry in entries {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            // the metadata is removed first, so that it never refers to a body
+            // that isn't there anymore
+            fs::remove_file(&entry.meta_path)?;
+            let _ = fs::remove_file(&entry.body_path);
+            total_bytes -= entry.bytes;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn paths(&self, url: &str) -> (PathBuf, PathBuf) {
+        let key = format!("{:016x}", fnv1a(url.as_bytes()));
+        (
+            self.dirpath.join(format!("{key}.json")),
+            self.dirpath.join(format!("{key}.body")),
+        )
+    }
+}
+
+/// Writes to a temporary file that is unique to this write, and then renames it
+/// over the target, so that concurrent writes never interleave.
+fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp_path, content)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// The 64-bit FNV-1a hash, which unlike the hasher of the standard library is
+/// the same across builds, so that the cache can be reused.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> Response {
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::ETAG, HeaderValue::from_static("\"v1\""));
+        Response {
+            url: "https://example.com/final".to_owned(),
+            status: StatusCode::OK,
+            headers,
+            body: body.as_bytes().to_vec(),
+            not_modified: false,
+        }
+    }
+
+    fn cache() -> HttpCache {
+        let dirpath = std::env::temp_dir().join(format!(
+            "linkstitcher-http-cache-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = fs::remove_dir_all(&dirpath);
+        HttpCache::new(dirpath).unwrap()
+    }
+
+    #[test]
+    fn stores_and_loads_responses() {
+        let cache = cache();
+        assert!(cache.load("https://example.com/").unwrap().is_none());
+
+        cache
+            .store("https://example.com/", &response("hello"))
+            .unwrap();
+        let cached = cache.load("https://example.com/").unwrap().unwrap();
+        assert_eq!(cached.url, "https://example.com/final");
+        assert_eq!(cached.body, b"hello");
+        assert_eq!(cached.headers.get(reqwest::header::ETAG).unwrap(), "\"v1\"");
+
+        // no temporary files are left behind
+        let names: Vec<_> = fs::read_dir(&cache.dirpath)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names.len(), 2, "{names:?}");
+        fs::remove_dir_all(&cache.dirpath).unwrap();
+    }
+
+    #[test]
+    fn evicts_the_least_recently_fetched_responses() {
+        let cache = cache();
+        let urls = [
+            "https://example.com/1",
+            "https://example.com/2",
+            "https://example.com/3",
+        ];
+        for (i, url) in urls.iter().enumerate() {
+            cache.store(url, &response(&"x".repeat(1000))).unwrap();
+            let (meta_path, _) = cache.paths(url);
+            let fetched_at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(i as u64);
+            fs::File::options()
+                .write(true)
+                .open(meta_path)
+                .unwrap()
+                .set_modified(fetched_at)
+                .unwrap();
+        }
+        // the first response was fetched again most recently
+        cache.touch(urls[0]).unwrap();
+
+        assert_eq!(cache.evict(u64::MAX).unwrap(), 0);
+        assert_eq!(cache.evict(1500).unwrap(), 2);
+        assert!(cache.load(urls[0]).unwrap().is_some());
+        assert!(cache.load(urls[1]).unwrap().is_none());
+        assert!(cache.load(urls[2]).unwrap().is_none());
+        fs::remove_dir_all(&cache.dirpath).unwrap();
+    }
+}
@@ -0,0 +1,309 @@
+//! Fetching statuses from Mastodon, and other fediverse servers that implement
+//! its [API](https://docs.joinmastodon.org/methods/statuses/#get), on any
+//! instance.
+//!
+//! A status is recognized by the shape of its URL, e.g.
+//! `https://mastodon.social/@Gargron/109336443573457386`, since instances can
+//! be on any host. The status is then fetched from the instance that the URL
+//! is on, from `/api/v1/statuses/:id`.
+use crate::utility::{self, http::Fetcher};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use url::Url;
+
+/// A status, as identified by its URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusRef {
+    /// The scheme and host of the instance, e.g. `https://mastodon.social`.
+    pub instance: String,
+    pub id: String,
+}
+
+impl StatusRef {
+    /// Parses a URL of the web interface of a status, like
+    /// `/@{user}/{id}`, `/@{user}@{domain}/{id}`, `/users/{user}/statuses/{id}`
+    /// or `/web/statuses/{id}`, on any host.
+    pub fn parse(url: &str) -> Option<StatusRef> {
+        let url = Url::parse(url).ok()?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        let id = match segments[..] {
+            [user, id] | ["web", user, id] if user.len() > 1 && user.starts_with('@') => id,
+            ["users", _, "statuses", id] | ["web", "statuses", id] => id,
+            _ => return None,
+        };
+        if !id.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let mut instance = format!("{}://{}", url.scheme(), url.host_str()?);
+        if let Some(port) = url.port() {
+            instance.push_str(&format!(":{port}"));
+        }
+        Some(StatusRef {
+            instance,
+            id: id.to_owned(),
+        })
+    }
+
+    pub fn api_url(&self) -> String {
+        format!("{}/api/v1/statuses/{}", self.instance, self.id)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Status {
+    pub id: String,
+    /// The URL of the status on the instance that it was posted on.
+    pub url: Option<String>,
+    pub created_at: String,
+    /// The HTML of the status.
+    pub content: String,
+    /// The content warning, if there is one.
+    #[serde(default)]
+    pub spoiler_text: String,
+    pub account: Account,
+    #[serde(default)]
+    pub media_attachments: Vec<MediaAttachment>,
+    /// The preview of the first link in the status.
+    pub card: Option<Card>,
+    /// The status that this status boosts.
+    pub reblog: Option<Box<Status>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    /// `user` for accounts on the same instance, or else `user@domain`.
+    pub acct: String,
+    #[serde(default)]
+    pub display_name: String,
+    /// The profile of the account, on its own instance.
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MediaAttachment {
+    #[serde(rename = "type")]
+    pub media_type: String,
+    /// The alt text.
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Card {
+    pub url: String,
+}
+
+impl Status {
+    /// The handle of the author, like `@user@domain`, with the domain of their
+    /// instance even if it's the one that the status was fetched from.
+    pub fn author_handle(&self) -> String {
+        if self.account.acct.contains('@') {
+            return format!("@{}", self.account.acct);
+        }
+        match Url::parse(&self.account.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+        {
+            Some(domain) => format!("@{}@{domain}", self.account.acct),
+            None => format!("@{}", self.account.acct),
+        }
+    }
+
+    pub fn author_name(&self) -> &str {
+        if self.account.display_name.trim().is_empty() {
+            &self.account.acct
+        } else {
+            self.account.display_name.trim()
+        }
+    }
+
+    pub fn posted(&self) -> Option<DateTime<Utc>> {
+        utility::date::parse_date(&self.created_at)
+    }
+
+    /// The text of the status, after its content warning if it has one.
+    pub fn text(&self) -> String {
+        let text = html_to_text(&self.content);
+        if self.spoiler_text.trim().is_empty() {
+            text
+        } else {
+            format!("CW: {}\n\n{text}", self.spoiler_text.trim())
+        }
+    }
+
+    /// The alt texts of the attached media.
+    pub fn alt_texts(&self) -> Vec<String> {
+        self.media_attachments
+            .iter()
+            .filter_map(|media| {
+                let alt = media.description.as_deref()?.trim();
+                (!alt.is_empty()).then(|| format!("{}: {alt}", media.media_type))
+            })
+            .collect()
+    }
+
+    /// The links in the status, other than mentions and hashtags, and the link
+    /// of its card.
+    pub fn links(&self) -> Vec<String> {
+        let html = scraper::Html::parse_fragment(&self.content);
+        let selector = scraper::Selector::parse("a[href]").unwrap();
+        let mut links: Vec<String> = vec![];
+        for link in html.select(&selector) {
+            let classes: Vec<&str> = link.value().classes().collect();
+            let is_tag = link
+                .value()
+                .attr("rel")
+                .is_some_and(|rel| rel.contains("tag"));
+            if classes.contains(&"mention") || classes.contains(&"hashtag") || is_tag {
+                continue;
+            }
+            if let Some(href) = link.value().attr("href")
+                && !links.iter().any(|l| l == href)
+            {
+                links.push(href.to_owned());
+            }
+        }
+        if let Some(card) = &self.card
+            && !links.contains(&card.url)
+        {
+            links.push(card.url.clone());
+        }
+        links
+    }
+}
+
+/// The plain text of the HTML of a status, with its paragraphs and line breaks.
+fn html_to_text(html: &str) -> String {
+    let html = html
+        .replace("<br />", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br>", "\n");
+    let html = scraper::Html::parse_fragment(&html);
+    let selector = scraper::Selector::parse("p").unwrap();
+    let paragraphs: Vec<String> = html
+        .select(&selector)
+        .map(|p| p.text().collect::<String>().trim().to_owned())
+        .collect();
+    if paragraphs.is_empty() {
+        html.root_element()
+            .text()
+            .collect::<String>()
+            .trim()
+            .to_owned()
+    } else {
+        paragraphs.join("\n\n")
+    }
+}
+
+/// Fetches the status from the instance in its URL. A status that boosts
+/// another one is resolved to the one that it boosts.
+pub async fn fetch_status(fetcher: &Fetcher, status_ref: &StatusRef) -> Result<Status> {
+    let status: Status = fetcher.get(&status_ref.api_url()).await?.json()?;
+    Ok(match status.reblog {
+        Some(reblog) => *reblog,
+        None => status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::mock_server::mock_server;
+
+    const STATUS: &str = r#"{
+        "id": "109336443573457386",
+        "url": "https://mastodon.social/@ada/109336443573457386",
+        "created_at": "2026-10-01T12:34:56.000Z",
+        "content": "<p>Effects, at last <a href=\"https://mastodon.social/@bob\" class=\"u-url mention\">@<span>bob</span></a>:<br />read <a href=\"https://example.com/effects\" rel=\"nofollow noopener\" target=\"_blank\"><span class=\"invisible\">https://</span><span class=\"\">example.com/effects</span></a></p><p><a href=\"https://mastodon.social/tags/ocaml\" class=\"mention hashtag\" rel=\"tag\">#<span>ocaml</span></a></p>",
+        "spoiler_text": "",
+        "account": {
+            "acct": "ada",
+            "display_name": "Ada Lovelace",
+            "url": "https://mastodon.social/@ada"
+        },
+        "media_attachments": [
+            {"type": "image", "description": "A diagram of a handler"},
+            {"type": "image", "description": null}
+        ],
+        "card": {"url": "https://example.com/card"},
+        "reblog": null
+    }"#;
+
+    const BOOST: &str = r#"{
+        "id": "2",
+        "url": null,
+        "created_at": "2026-10-02T00:00:00.000Z",
+        "content": "",
+        "account": {"acct": "bob", "display_name": "", "url": "https://mastodon.social/@bob"},
+        "reblog": {
+            "id": "109336443573457386",
+            "url": "https://mastodon.social/@ada/109336443573457386",
+            "created_at": "2026-10-01T12:34:56.000Z",
+            "content": "<p>Effects, at last</p>",
+            "account": {"acct": "ada", "display_name": "Ada Lovelace", "url": "https://mastodon.social/@ada"}
+        }
+    }"#;
+
+    #[test]
+    fn parses_status_urls() {
+        let status_ref = |url| StatusRef::parse(url).map(|status_ref| status_ref.api_url());
+        assert_eq!(
+            status_ref("https://mastodon.social/@ada/109336443573457386").as_deref(),
+            Some("https://mastodon.social/api/v1/statuses/109336443573457386")
+        );
+        assert_eq!(
+            status_ref("https://hachyderm.io/@ada@mastodon.social/1").as_deref(),
+            Some("https://hachyderm.io/api/v1/statuses/1")
+        );
+        assert_eq!(
+            status_ref("https://fosstodon.org/users/ada/statuses/2").as_deref(),
+            Some("https://fosstodon.org/api/v1/statuses/2")
+        );
+        assert_eq!(
+            status_ref("https://fosstodon.org/web/statuses/3").as_deref(),
+            Some("https://fosstodon.org/api/v1/statuses/3")
+        );
+        assert_eq!(status_ref("https://mastodon.social/@ada"), None);
+        assert_eq!(status_ref("https://medium.com/@ada/effects-1a2b3c"), None);
+        assert_eq!(status_ref("https://example.com/blog/2026"), None);
+    }
+
+    #[tokio::test]
+    async fn fetches_statuses() {
+        let instance = mock_server(vec![
+            ("/api/v1/statuses/109336443573457386", STATUS),
+            ("/api/v1/statuses/2", BOOST),
+        ])
+        .await;
+        let fetcher = Fetcher::new().unwrap();
+
+        let status_ref = StatusRef::parse(&format!("{instance}/@ada/109336443573457386")).unwrap();
+        let status = fetch_status(&fetcher, &status_ref).await.unwrap();
+        assert_eq!(status.author_name(), "Ada Lovelace");
+        assert_eq!(status.author_handle(), "@ada@mastodon.social");
+        assert_eq!(
+            status.posted().map(|posted| posted.to_rfc3339()).as_deref(),
+            Some("2026-10-01T12:34:56+00:00")
+        );
+        assert_eq!(
+            status.text(),
+            "Effects, at last @bob:\nread https://example.com/effects\n\n#ocaml"
+        );
+        assert_eq!(status.alt_texts(), vec!["image: A diagram of a handler"]);
+        assert_eq!(
+            status.links(),
+            vec!["https://example.com/effects", "https://example.com/card"]
+        );
+
+        // a boost is resolved to the status that it boosts
+        let status_ref = StatusRef::parse(&format!("{instance}/web/statuses/2")).unwrap();
+        let status = fetch_status(&fetcher, &status_ref).await.unwrap();
+        assert_eq!(status.id, "109336443573457386");
+
+        let status_ref = StatusRef::parse(&format!("{instance}/@ada/3")).unwrap();
+        assert!(fetch_status(&fetcher, &status_ref).await.is_err());
+    }
+}
@@ -0,0 +1,301 @@
+//! The metadata that HTML pages declare about themselves.
+//!
+//! Each field is generally taken from the first of these that has it:
+//! - the [OpenGraph](https://ogp.me) `og:*` and `article:*` meta tags,
+//! - the [Twitter Card](https://developer.x.com/en/docs/x-for-websites/cards)
+//!   `twitter:*` meta tags,
+//! - a schema.org JSON-LD block that describes an `Article` or one of its
+//!   subtypes, rather than e.g. the `WebSite` or `Organization` of the page,
+//! - the plain `author`, `description` and `application-name` meta tags and the
+//!   `<title>`.
+//!
+//! The exception is the author, for which a `twitter:creator` handle is the
+//! last resort.
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Default)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub site_name: Option<String>,
+    pub image_url: Option<String>,
+    pub published_time: Option<String>,
+}
+
+/// The schema.org types of JSON-LD blocks that describe an article.
+const ARTICLE_TYPES: [&str; 8] = [
+    "Article",
+    "NewsArticle",
+    "BlogPosting",
+    "TechArticle",
+    "ScholarlyArticle",
+    "Report",
+    "SocialMediaPosting",
+    "AnalysisNewsArticle",
+];
+
+/// Extracts the metadata of the HTML page at `page_url`.
+pub fn extract(html: &str, page_url: &str) -> PageMetadata {
+    let document = Html::parse_document(html);
+    let json_ld = json_ld_article(&document).unwrap_or(Value::Null);
+
+    let title = meta(&document, "og:title")
+        .or_else(|| meta(&document, "twitter:title"))
+        .or_else(|| json_ld_string(&json_ld, "headline"))
+        .or_else(|| json_ld_string(&json_ld, "name"))
+        .or_else(|| element_text(&document, "title"));
+
+    let description = meta(&document, "og:description")
+        .or_else(|| meta(&document, "twitter:description"))
+        .or_else(|| json_ld_string(&json_ld, "description"))
+        .or_else(|| meta(&document, "description"));
+
+    // `article:author` is often the URL of the author's profile
+    let author = meta(&document, "article:author")
+        .filter(|author| !is_url(author))
+        .or_else(|| json_ld_names(&json_ld, "author"))
+        .or_else(|| meta(&document, "author"))
+        .or_else(|| meta(&document, "twitter:creator"));
+
+    let site_name = meta(&document, "og:site_name")
+        .or_else(|| json_ld_names(&json_ld, "publisher"))
+        .or_else(|| meta(&document, "application-name"));
+
+    let image_url = meta(&document, "og:image")
+        .or_else(|| meta(&document, "og:image:url"))
+        .or_else(|| meta(&document, "twitter:image"))
+        .or_else(|| json_ld_image(&json_ld))
+        .and_then(|image_url| resolve(page_url, &image_url));
+
+    let published_time = meta(&document, "article:published_time")
+        .or_else(|| json_ld_string(&json_ld, "datePublished"))
+        .or_else(|| meta(&document, "date"));
+
+    PageMetadata {
+        title,
+        description,
+        author,
+        site_name,
+        image_url,
+        published_time,
+    }
+}
+
+/// The content of the first meta tag with the property or name.
+fn meta(document: &Html, key: &str) -> Option<String> {
+    let selector =
+        Selector::parse(&format!("meta[property=\"{key}\"], meta[name=\"{key}\"]")).ok()?;
+    document
+        .select(&selector)
+        .filter_map(|element| element.value().attr("content"))
+        .find_map(clean)
+}
+
+fn element_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    clean(
+        &document
+            .select(&selector)
+            .next()?
+            .text()
+            .collect::<String>(),
+    )
+}
+
+/// Collapses the whitespace of the text. Returns `None` if nothing is left.
+fn clean(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn is_url(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+fn resolve(page_url: &str, url: &str) -> Option<String> {
+    let url = url::Url::parse(page_url).ok()?.join(url).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// The first JSON-LD object that describes an article. Other objects, like the
+/// `WebSite` or `Organization` that publishes the page, aren't about the page.
+fn json_ld_article(document: &Html) -> Option<Value> {
+    let selector = Selector::parse("script[type=\"application/ld+json\"]").ok()?;
+    let mut objects = vec![];
+    for script in document.select(&selector) {
+        let text = script.text().collect::<String>();
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) => collect_objects(value, &mut objects),
+            Err(e) => log::debug!["invalid JSON-LD: {e}"],
+        }
+    }
+
+    let is_article = |object: &Value| match object.get("@type") {
+        Some(Value::String(t)) => ARTICLE_TYPES.contains(&t.as_str()),
+        Some(Value::Array(ts)) => ts
+            .iter()
+            .any(|t| t.as_str().is_some_and(|t| ARTICLE_TYPES.contains(&t))),
+        _ => false,
+    };
+    objects.into_iter().find(is_article)
+}
+
+/// Flattens arrays and `@graph`s of JSON-LD objects.
+fn collect_objects(value: Value, objects: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_objects(value, objects);
+            }
+        }
+        Value::Object(mut object) => {
+            if let Some(graph) = object.remove("@graph") {
+                collect_objects(graph, objects);
+            }
+            if !object.is_empty() {
+                objects.push(Value::Object(object));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn json_ld_string(object: &Value, key: &str) -> Option<String> {
+    clean(object.get(key)?.as_str()?)
+}
+
+/// The names of a JSON-LD property whose values are people or organizations,
+/// given as strings, objects with a `name`, or arrays of either.
+fn json_ld_names(object: &Value, key: &str) -> Option<String> {
+    let name = |value: &Value| match value {
+        Value::String(name) => clean(name).filter(|name| !is_url(name)),
+        Value::Object(_) => json_ld_string(value, "name"),
+        _ => None,
+    };
+    let names: Vec<String> = match object.get(key)? {
+        Value::Array(values) => values.iter().filter_map(name).collect(),
+        value => name(value).into_iter().collect(),
+    };
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+/// The URL of a JSON-LD `image`, given as a string, an `ImageObject`, or an
+/// array of either.
+fn json_ld_image(object: &Value) -> Option<String> {
+    let url = |value: &Value| match value {
+        Value::String(url) => clean(url),
+        Value::Object(_) => json_ld_string(value, "url"),
+        _ => None,
+    };
+    match object.get("image")? {
+        Value::Array(values) => values.iter().find_map(url),
+        value => url(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_opengraph_and_twitter_tags() {
+        let metadata = extract(
+            r#"<html><head>
+                <title>Page title</title>
+                <meta property="og:title" content="  OpenGraph
+                    title ">
+                <meta name="twitter:title" content="Twitter title">
+                <meta name="twitter:description" content="Twitter description">
+                <meta name="description" content="Plain description">
+                <meta property="article:author" content="https://example.com/ada">
+                <meta name="author" content="Ada Lovelace">
+                <meta name="twitter:creator" content="@ada">
+                <meta property="og:site_name" content="Example">
+                <meta property="og:image" content="/images/cover.png">
+                <meta property="article:published_time" content="2026-10-17T12:00:00Z">
+            </head></html>"#,
+            "https://example.com/posts/1",
+        );
+        assert_eq!(metadata.title.as_deref(), Some("OpenGraph title"));
+        assert_eq!(metadata.description.as_deref(), Some("Twitter description"));
+        assert_eq!(metadata.author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(metadata.site_name.as_deref(), Some("Example"));
+        assert_eq!(
+            metadata.image_url.as_deref(),
+            Some("https://example.com/images/cover.png")
+        );
+        assert_eq!(
+            metadata.published_time.as_deref(),
+            Some("2026-10-17T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_json_ld_articles() {
+        let metadata = extract(
+            r#"<html><head>
+                <title>Page title</title>
+                <script type="application/ld+json">{
+                    "@context": "https://schema.org",
+                    "@graph": [
+                        {"@type": "WebSite", "name": "Example", "description": "A site"},
+                        {
+                            "@type": ["BlogPosting"],
+                            "headline": "JSON-LD headline",
+                            "description": "JSON-LD description",
+                            "author": [{"@type": "Person", "name": "Ada"}, "Grace"],
+                            "publisher": {"@type": "Organization", "name": "Example Press"},
+                            "image": {"@type": "ImageObject", "url": "cover.png"},
+                            "datePublished": "2026-10-17"
+                        }
+                    ]
+                }</script>
+                <script type="application/ld+json">not JSON</script>
+            </head></html>"#,
+            "https://example.com/posts/1",
+        );
+        assert_eq!(metadata.title.as_deref(), Some("JSON-LD headline"));
+        assert_eq!(metadata.description.as_deref(), Some("JSON-LD description"));
+        assert_eq!(metadata.author.as_deref(), Some("Ada, Grace"));
+        assert_eq!(metadata.site_name.as_deref(), Some("Example Press"));
+        assert_eq!(
+            metadata.image_url.as_deref(),
+            Some("https://example.com/posts/cover.png")
+        );
+        assert_eq!(metadata.published_time.as_deref(), Some("2026-10-17"));
+    }
+
+    #[test]
+    fn ignores_json_ld_that_isnt_an_article() {
+        let metadata = extract(
+            r#"<html><head>
+                <title>Page title</title>
+                <meta name="description" content="Plain description">
+                <meta name="application-name" content="Example app">
+                <script type="application/ld+json">[
+                    {"@type": "WebSite", "name": "Example", "description": "A site"},
+                    {"@type": "Organization", "name": "Example Inc", "image": "logo.png"}
+                ]</script>
+            </head></html>"#,
+            "https://example.com/posts/1",
+        );
+        assert_eq!(metadata.title.as_deref(), Some("Page title"));
+        assert_eq!(metadata.description.as_deref(), Some("Plain description"));
+        assert_eq!(metadata.site_name.as_deref(), Some("Example app"));
+        assert_eq!(metadata.image_url, None);
+        assert_eq!(metadata.author, None);
+    }
+
+    #[test]
+    fn only_resolves_http_image_urls() {
+        let metadata = extract(
+            r#"<meta property="og:image" content="javascript:alert(1)">
+               <meta name="twitter:creator" content="@ada">"#,
+            "https://example.com/",
+        );
+        assert_eq!(metadata.image_url, None);
+        assert_eq!(metadata.author.as_deref(), Some("@ada"));
+    }
+}
@@ -0,0 +1,161 @@
+//! Working out what kind of content a response has.
+//!
+//! The `Content-Type` header is trusted unless it is missing, unparsable or
+//! only says the content is binary, in which case the type is sniffed from the
+//! content's magic bytes, and then guessed from the extension of its URL.
+use mime::Mime;
+
+/// Content types that only say that the content is binary.
+const GENERIC_CONTENT_TYPES: [&str; 4] = [
+    "application/octet-stream",
+    "binary/octet-stream",
+    "application/x-download",
+    "application/force-download",
+];
+
+/// The kinds of content that are handled differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Html,
+    Pdf,
+    Markdown,
+    Text,
+    Image,
+    Binary,
+}
+
+impl ContentKind {
+    pub fn of(mime: &Mime) -> Self {
+        match (mime.type_().as_str(), mime.subtype().as_str()) {
+            // the subtype of `application/xhtml+xml` is `xhtml`, with an `xml` suffix
+            ("text", "html") | ("application", "xhtml") => ContentKind::Html,
+            ("application" | "text", "pdf" | "x-pdf") => ContentKind::Pdf,
+            ("text", "markdown" | "x-markdown") => ContentKind::Markdown,
+            ("text", _) => ContentKind::Text,
+            ("application", "json" | "xml") => ContentKind::Text,
+            ("image", _) => ContentKind::Image,
+            _ => ContentKind::Binary,
+        }
+    }
+}
+
+/// The content type of a response, from its `Content-Type` header if it has a
+/// useful one, and otherwise sniffed from its body or guessed from its URL.
+pub fn detect(content_type: Option<&str>, url: &str, body: &[u8]) -> Mime {
+    if let Some(mime) = content_type.and_then(|content_type| content_type.parse::<Mime>().ok())
+        && !GENERIC_CONTENT_TYPES.contains(&mime.essence_str())
+    {
+        return mime;
+    }
+
+    sniff(body)
+        .or_else(|| guess_from_url(url))
+        .unwrap_or_else(|| {
+            if !body.contains(&0) && std::str::from_utf8(body).is_ok() {
+                mime::TEXT_PLAIN_UTF_8
+            } else {
+                mime::APPLICATION_OCTET_STREAM
+            }
+        })
+}
+
+/// The content type that the magic bytes at the start of `body` identify.
+pub fn sniff(body: &[u8]) -> Option<Mime> {
+    let essence = if body.starts_with(b"%PDF-") {
+        "application/pdf"
+    } else if body.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if body.starts_with(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if body.starts_with(b"GIF87a") || body.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if body.len() >= 12 && body.starts_with(b"RIFF") && &body[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        let start = String::from_utf8_lossy(&body[..body.len().min(512)]).to_lowercase();
+        let start = start.trim_start_matches('\u{feff}').trim_start();
+        if start.starts_with("<!doctype html") || start.starts_with("<html") {
+            "text/html"
+        } else if start.starts_with("<svg") {
+            "image/svg+xml"
+        } else {
+            return None;
+        }
+    };
+    essence.parse().ok()
+}
+
+/// The content type that the extension of the path of `url` suggests.
+pub fn guess_from_url(url: &str) -> Option<Mime> {
+    let url = url::Url::parse(url).ok()?;
+    let file_name = url.path_segments()?.next_back()?.to_lowercase();
+    let (_, extension) = file_name.rsplit_once(".")?;
+    let essence = match extension {
+        "pdf" => "application/pdf",
+        "html" | "htm" => "text/html",
+        "md" | "markdown" => "text/markdown",
+        "txt" => "text/plain",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => return None,
+    };
+    essence.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDF: &[u8] = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n1 0 obj";
+    const HTML: &[u8] = b"\n  <!DOCTYPE html><html><head><title>Hi</title></head></html>";
+
+    #[test]
+    fn detects_content_types() {
+        #[rustfmt::skip]
+        let cases: [(Option<&str>, &str, &[u8], &str); 12] = [
+            // a useful header is trusted, whatever the body or URL say
+            (Some("text/html; charset=utf-8"), "https://example.com/a.pdf", PDF, "text/html"),
+            (Some("text/plain"), "https://example.com/", HTML, "text/plain"),
+            (Some("application/pdf"), "https://example.com/paper", b"", "application/pdf"),
+            // a generic, missing or unparsable header falls back to sniffing
+            (Some("application/octet-stream"), "https://example.com/download", PDF, "application/pdf"),
+            (Some("binary/octet-stream"), "https://example.com/a.html", HTML, "text/html"),
+            (None, "https://example.com/a.txt", PDF, "application/pdf"),
+            (Some("not a type"), "https://example.com/", b"\x89PNG\r\n\x1a\n....", "image/png"),
+            // and then to the URL's extension
+            (Some("application/octet-stream"), "https://example.com/paper.PDF?dl=1", b"\x00\x01", "application/pdf"),
+            (None, "https://example.com/README.md", b"# Title", "text/markdown"),
+            // and then to whether the body is text
+            (None, "https://example.com/notes", "café".as_bytes(), "text/plain"),
+            (None, "https://example.com/blob", b"\x00\x01\x02", "application/octet-stream"),
+            (None, "https://example.com/blob.unknown", b"\xff\xfe\xfd", "application/octet-stream"),
+        ];
+        for (content_type, url, body, expected) in cases {
+            assert_eq!(
+                detect(content_type, url, body).essence_str(),
+                expected,
+                "{content_type:?} {url}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_content_types() {
+        let cases = [
+            ("text/html", ContentKind::Html),
+            ("application/xhtml+xml", ContentKind::Html),
+            ("application/x-pdf", ContentKind::Pdf),
+            ("text/markdown", ContentKind::Markdown),
+            ("text/plain; charset=utf-8", ContentKind::Text),
+            ("application/json", ContentKind::Text),
+            ("image/svg+xml", ContentKind::Image),
+            ("application/zip", ContentKind::Binary),
+        ];
+        for (content_type, expected) in cases {
+            assert_eq!(ContentKind::of(&content_type.parse().unwrap()), expected);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! A local HTTP server for testing API clients without the network.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Serves the bodies of the given paths as JSON, whatever their query, and 404
+/// for any other path. Returns the base URL of the server, e.g.
+/// `http://127.0.0.1:8080`.
+pub async fn mock_server(routes: Vec<(&'static str, &'static str)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let n = stream.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..n]);
+            let target = request.split_whitespace().nth(1).unwrap_or_default();
+            let path = target.split('?').next().unwrap_or_default();
+            let response = match routes.iter().find(|(route, _)| *route == path) {
+                Some((_, body)) => format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                ),
+                None => "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    .to_owned(),
+            };
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    format!("http://{addr}")
+}
+
+/// How many requests a [`counting_server`] has handled at once, at most.
+#[derive(Debug, Default)]
+pub struct Peaks {
+    in_flight: HashMap<String, usize>,
+    /// The peak for each host, as named by the `Host` header, without its port.
+    pub per_host: HashMap<String, usize>,
+    pub total: usize,
+    total_in_flight: usize,
+}
+
+/// Serves every request concurrently, after waiting for as many milliseconds as
+/// its `delay` query parameter says, and records the peak number of requests in
+/// flight. Responds with the path as plain text, or with 500 for paths starting
+/// with `/fail`. Returns the port of the server, so that it can be reached as
+/// both `127.0.0.1` and `localhost`.
+pub async fn counting_server() -> (u16, Arc<Mutex<Peaks>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let peaks = Arc::new(Mutex::new(Peaks::default()));
+    let recorded = peaks.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let peaks = recorded.clone();
+            tokio::spawn(async move {
+                let mut request = vec![0; 4096];
+                let n = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..n]).into_owned();
+                let target = request.split_whitespace().nth(1).unwrap_or_default();
+                let url = url::Url::parse(&format!("http://server{target}")).unwrap();
+                let delay = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "delay")
+                    .and_then(|(_, value)| value.parse().ok())
+                    .unwrap_or(0);
+                let host = request
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("host").then(|| value.trim())
+                    })
+                    .unwrap_or_default()
+                    .split(':')
+                    .next()
+                    .unwrap_or_default()
+                    .to_owned();
+
+                {
+                    let mut peaks = peaks.lock().unwrap();
+                    let in_flight = peaks.in_flight.entry(host.clone()).or_default();
+                    *in_flight += 1;
+                    let in_flight = *in_flight;
+                    let peak = peaks.per_host.entry(host.clone()).or_default();
+                    *peak = (*peak).max(in_flight);
+                    peaks.total_in_flight += 1;
+                    peaks.total = peaks.total.max(peaks.total_in_flight);
+                }
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                {
+                    let mut peaks = peaks.lock().unwrap();
+                    *peaks.in_flight.get_mut(&host).unwrap() -= 1;
+                    peaks.total_in_flight -= 1;
+                }
+
+                let path = url.path();
+                let status = if path.starts_with("/fail") {
+                    "500 Internal Server Error"
+                } else {
+                    "200 OK"
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{path}",
+                    path.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+    (port, peaks)
+}
@@ -1,8 +1,27 @@
 pub mod ai;
 pub mod arxiv;
+pub mod arxiv_papers;
+pub mod bluesky;
+pub mod canonical;
+pub mod citation;
+pub mod date;
 pub mod db;
+pub mod feed;
 pub mod github;
+pub mod github_releases;
+pub mod hackernews;
+pub mod http;
+pub mod http_cache;
+pub mod mastodon;
+pub mod metadata;
+pub mod mime;
+#[cfg(test)]
+pub mod mock_server;
+pub mod pdf;
+pub mod readability;
 pub mod rss;
+pub mod search;
+pub mod tags;
 pub mod x;
 
 pub fn indent(s: &str) -> String {
@@ -0,0 +1,97 @@
+//! Extracting the text and metadata of PDFs.
+use anyhow::{Result, anyhow};
+use pdf_extract::{Document, Object, PlainTextOutput};
+
+#[derive(Debug, Clone)]
+pub struct Pdf {
+    pub text: String,
+    /// The title in the PDF's metadata, if it has a meaningful one.
+    pub title: Option<String>,
+    pub page_count: usize,
+}
+
+/// Extracts the text, title and page count of the PDF.
+pub fn extract(bytes: &[u8]) -> Result<Pdf> {
+    let mut document = Document::load_mem(bytes)?;
+    if document.is_encrypted() {
+        document.decrypt("")?;
+    }
+
+    let title = info_string(&document, b"Title").filter(|title| is_meaningful_title(title));
+    let page_count = document.get_pages().len();
+
+    // pdf_extract panics on some malformed PDFs. The document isn't used again
+    // after a panic, so it doesn't matter what state the panic leaves it in.
+    let text = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut text = String::new();
+        pdf_extract::output_doc(&document, &mut PlainTextOutput::new(&mut text))?;
+        Ok::<_, pdf_extract::OutputError>(text)
+    }))
+    .map_err(|_| anyhow!("pdf_extract panicked while extracting text"))??;
+
+    Ok(Pdf {
+        text,
+        title,
+        page_count,
+    })
+}
+
+/// The value of a string entry of the PDF's document information dictionary.
+fn info_string(document: &Document, key: &[u8]) -> Option<String> {
+    let info = document.trailer.get(b"Info").ok()?;
+    let (_, info) = document.dereference(info).ok()?;
+    let value = info.as_dict().ok()?.get(key).ok()?;
+    let (_, value) = document.dereference(value).ok()?;
+    match value {
+        Object::String(..) => Some(
+            pdf_extract::decode_text_string(value)
+                .ok()?
+                .trim()
+                .to_owned(),
+        ),
+        _ => None,
+    }
+}
+
+/// Whether the title is more than what tools fill in by default, like
+/// `untitled` or the name of the file that the PDF was made from.
+fn is_meaningful_title(title: &str) -> bool {
+    let lowercase = title.to_lowercase();
+    !(title.is_empty()
+        || lowercase == "untitled"
+        || lowercase.starts_with("microsoft word - ")
+        || [".doc", ".docx", ".dvi", ".pdf", ".tex"]
+            .iter()
+            .any(|extension| lowercase.ends_with(extension)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_PAGES: &[u8] = include_bytes!("../../tests/fixtures/pdf/two-pages.pdf");
+
+    #[test]
+    fn extracts_text_and_metadata() {
+        let pdf = extract(TWO_PAGES).unwrap();
+        assert_eq!(pdf.page_count, 2);
+        assert_eq!(pdf.title.as_deref(), Some("A Tale of Two Pages"));
+        assert!(pdf.text.contains("page one"), "{}", pdf.text);
+        assert!(pdf.text.contains("page two"), "{}", pdf.text);
+        assert!(extract(b"%PDF-1.4 but not really").is_err());
+    }
+
+    #[test]
+    fn ignores_default_titles() {
+        for title in [
+            "",
+            "Untitled",
+            "Microsoft Word - draft",
+            "paper.tex",
+            "main.pdf",
+        ] {
+            assert!(!is_meaningful_title(title), "{title}");
+        }
+        assert!(is_meaningful_title("Attention Is All You Need"));
+    }
+}
@@ -0,0 +1,29 @@
+//! Extracting the article of an HTML page with Readability, on the blocking
+//! thread pool.
+//!
+//! Readability runs in a JavaScript engine, which is CPU-bound and would hold
+//! up every other preview that is being embellished on the same async task.
+//! The engine can't be moved between threads, so each blocking thread creates
+//! its own the first time that it's needed, and keeps it.
+use anyhow::Result;
+use readability_js::{Article, Readability};
+use std::cell::RefCell;
+
+thread_local! {
+    static READABILITY: RefCell<Option<Readability>> = const { RefCell::new(None) };
+}
+
+/// Extracts the article of the HTML page at `url`.
+pub async fn parse(html: String, url: String) -> Result<Article> {
+    tokio::task::spawn_blocking(move || {
+        READABILITY.with(|readability| {
+            let mut readability = readability.borrow_mut();
+            if readability.is_none() {
+                *readability = Some(Readability::new()?);
+            }
+            let readability = readability.as_ref().expect("created above");
+            Ok(readability.parse_with_url(&html, &url)?)
+        })
+    })
+    .await?
+}
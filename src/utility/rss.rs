@@ -1,4 +1,4 @@
-use crate::{config, models::Preview};
+use crate::{config, models::Preview, utility::http::Fetcher};
 use anyhow::Result;
 use std::{fs::File, io::BufWriter};
 
@@ -31,9 +31,14 @@ pub fn write_rss_channel(file_path: &str, channel: rss::Channel) -> Result<()> {
     Ok(())
 }
 
-pub async fn fetch_rss_channel(client: &reqwest::Client, url: &str) -> Result<rss::Channel> {
-    let content = client.get(url).send().await?;
-    let bytes = content.bytes().await?;
-    let channel = rss::Channel::read_from(&bytes[..])?;
-    Ok(channel)
+/// Fetches the channel, unless it hasn't been modified since it was last
+/// fetched, in which case there is nothing new in it.
+pub async fn fetch_rss_channel(fetcher: &Fetcher, url: &str) -> Result<Option<rss::Channel>> {
+    let response = fetcher.get(url).await?;
+    if response.not_modified {
+        log::info!["fetch_rss_channel: not modified: {url}"];
+        return Ok(None);
+    }
+    let channel = rss::Channel::read_from(&response.body[..])?;
+    Ok(Some(channel))
 }
@@ -0,0 +1,201 @@
+//! Full-text search over previews, backed by the `previews_fts` FTS5 table.
+use crate::models::Preview;
+use anyhow::Result;
+use diesel::{
+    prelude::*,
+    sql_types::{BigInt, Bool, Double, Nullable, Text},
+};
+
+/// Restricts which previews a search may return.
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    pub saved: Option<bool>,
+    pub bookmarked: Option<bool>,
+    pub source: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct SearchResult {
+    #[diesel(embed)]
+    pub preview: Preview,
+    /// The BM25 score of the match. Lower is better.
+    #[diesel(sql_type = Double)]
+    pub rank: f64,
+    /// An excerpt of the best-matching column, with matches in `[` and `]`.
+    #[diesel(sql_type = Text)]
+    pub snippet: String,
+}
+
+pub const DEFAULT_SEARCH_LIMIT: i64 = 20;
+
+/// Searches the title, summary, tags and content of previews, best matches
+/// first. `query` uses the FTS5 query syntax, so it may use phrases, prefixes
+/// and boolean operators, e.g. `"type theory" OR hask*`.
+pub fn search_previews(
+    db_conn: &mut SqliteConnection,
+    query: &str,
+    filters: &SearchFilters,
+) -> Result<Vec<SearchResult>> {
+    // matches in titles weigh more than matches in tags and summaries, which
+    // weigh more than matches in content
+    let results = diesel::sql_query(
+        "SELECT previews.*, \
+           bm25(previews_fts, 0.0, 10.0, 5.0, 5.0, 1.0) AS rank, \
+           snippet(previews_fts, -1, '[', ']', '...', 16) AS snippet \
+         FROM previews_fts \
+         JOIN previews ON previews.url = previews_fts.url \
+         WHERE previews_fts MATCH ? \
+           AND (? IS NULL OR previews.saved = ?) \
+           AND (? IS NULL OR previews.bookmarked = ?) \
+           AND (? IS NULL OR previews.source = ?) \
+         ORDER BY rank \
+         LIMIT ?",
+    )
+    .into_boxed()
+    .bind::<Text, _>(query)
+    .bind::<Nullable<Bool>, _>(filters.saved)
+    .bind::<Nullable<Bool>, _>(filters.saved)
+    .bind::<Nullable<Bool>, _>(filters.bookmarked)
+    .bind::<Nullable<Bool>, _>(filters.bookmarked)
+    .bind::<Nullable<Text>, _>(&filters.source)
+    .bind::<Nullable<Text>, _>(&filters.source)
+    .bind::<BigInt, _>(filters.limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+    .load::<SearchResult>(db_conn)?;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::db;
+
+    fn insert(db_conn: &mut SqliteConnection, url: &str, title: &str, summary: &str) -> Preview {
+        let mut preview = Preview::from_url(url.to_owned());
+        preview.title = Some(title.to_owned());
+        preview.summary = Some(summary.to_owned());
+        db::insert_preview(db_conn, &preview).unwrap();
+        preview
+    }
+
+    fn urls(db_conn: &mut SqliteConnection, query: &str, filters: &SearchFilters) -> Vec<String> {
+        search_previews(db_conn, query, filters)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.preview.url)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_index_in_sync() {
+        let mut db_conn = db::test_connection();
+        let mut preview = insert(
+            &mut db_conn,
+            "https://example.com/a",
+            "Monads",
+            "An introduction",
+        );
+        let filters = SearchFilters::default();
+        assert_eq!(
+            urls(&mut db_conn, "monad", &filters),
+            ["https://example.com/a"]
+        );
+
+        preview.title = Some("Effects".to_owned());
+        db::update_preview(&mut db_conn, &preview).unwrap();
+        assert!(urls(&mut db_conn, "monad", &filters).is_empty());
+        assert_eq!(
+            urls(&mut db_conn, "effects", &filters),
+            ["https://example.com/a"]
+        );
+
+        assert!(db::delete_preview(&mut db_conn, &preview.url).unwrap());
+        assert!(urls(&mut db_conn, "effects", &filters).is_empty());
+    }
+
+    #[test]
+    fn ranks_title_matches_first() {
+        let mut db_conn = db::test_connection();
+        insert(
+            &mut db_conn,
+            "https://example.com/summary",
+            "Notes",
+            "On type theory and more",
+        );
+        insert(
+            &mut db_conn,
+            "https://example.com/title",
+            "Type theory",
+            "Some notes",
+        );
+        insert(
+            &mut db_conn,
+            "https://example.com/other",
+            "Cooking",
+            "Some recipes",
+        );
+
+        let results =
+            search_previews(&mut db_conn, "\"type theory\"", &SearchFilters::default()).unwrap();
+        let urls: Vec<_> = results
+            .iter()
+            .map(|result| &result.preview.url[..])
+            .collect();
+        assert_eq!(
+            urls,
+            ["https://example.com/title", "https://example.com/summary"]
+        );
+        assert!(results[0].rank <= results[1].rank);
+        assert!(
+            results[0].snippet.contains("[Type theory]"),
+            "{}",
+            results[0].snippet
+        );
+    }
+
+    #[test]
+    fn filters_results() {
+        let mut db_conn = db::test_connection();
+        let mut saved = Preview::from_url("https://example.com/saved".to_owned());
+        saved.title = Some("Rust".to_owned());
+        saved.saved = true;
+        saved.source = Some("Hacker News".to_owned());
+        db::insert_preview(&mut db_conn, &saved).unwrap();
+        let mut bookmarked = Preview::from_url("https://example.com/bookmarked".to_owned());
+        bookmarked.title = Some("Rust".to_owned());
+        bookmarked.bookmarked = true;
+        db::insert_preview(&mut db_conn, &bookmarked).unwrap();
+
+        let all = SearchFilters::default();
+        assert_eq!(urls(&mut db_conn, "rust", &all).len(), 2);
+        let only_saved = SearchFilters {
+            saved: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            urls(&mut db_conn, "rust", &only_saved),
+            ["https://example.com/saved"]
+        );
+        let only_bookmarked = SearchFilters {
+            bookmarked: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            urls(&mut db_conn, "rust", &only_bookmarked),
+            ["https://example.com/bookmarked"]
+        );
+        let by_source = SearchFilters {
+            source: Some("Hacker News".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            urls(&mut db_conn, "rust", &by_source),
+            ["https://example.com/saved"]
+        );
+        let limited = SearchFilters {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(urls(&mut db_conn, "rust", &limited).len(), 1);
+    }
+}
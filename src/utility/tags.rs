@@ -0,0 +1,388 @@
+//! Normalized tags of previews.
+//!
+//! Tags are stored in the `tags` table and related to previews by the
+//! `preview_tags` table, which also records each tag's [`TagProvenance`].
+//! `previews.tags` is kept as the comma-separated list of a preview's
+//! normalized tag names, so it can still be read directly.
+use crate::models::*;
+use anyhow::Result;
+use diesel::{
+    dsl::{exists, not},
+    prelude::*,
+    sql_types::Text,
+};
+use std::str::FromStr;
+
+/// Normalizes the name of a tag by lowercasing it and collapsing its
+/// whitespace. Returns `None` if nothing is left.
+pub fn normalize_tag(name: &str) -> Option<String> {
+    let name = name
+        .trim_matches(|c: char| c.is_whitespace() || c == '.')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if name.is_empty() { None } else { Some(name) }
+}
+
+/// Normalizes a comma-separated list of tags, dropping duplicates.
+pub fn normalize_tags(tags: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for name in tags.split(",").filter_map(normalize_tag) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct TagCount {
+    #[diesel(sql_type = Text)]
+    pub name: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub count: i64,
+}
+
+fn get_or_create_tag(db_conn: &mut SqliteConnection, name: &str) -> Result<i32> {
+    use crate::schema::tags::dsl;
+
+    diesel::insert_or_ignore_into(dsl::tags)
+        .values(dsl::name.eq(name))
+        .execute(db_conn)?;
+    Ok(dsl::tags
+        .filter(dsl::name.eq(name))
+        .select(dsl::id)
+        .first(db_conn)?)
+}
+
+/// Relates `preview` to the tags in `preview.tags`, with the provenance in
+/// `preview.tags_provenance`. Tags of that provenance that are no longer in
+/// `preview.tags` are removed. The preview must already be stored.
+pub fn sync_preview_tags(db_conn: &mut SqliteConnection, preview: &Preview) -> Result<()> {
+    use crate::schema::preview_tags::dsl;
+
+    if preview.tags.is_none() && preview.tags_provenance.is_none() {
+        return Ok(());
+    }
+
+    let provenance = match &preview.tags_provenance {
+        None => TagProvenance::Manual,
+        Some(provenance) => TagProvenance::from_str(provenance)?,
+    };
+    let names = normalize_tags(preview.tags.as_deref().unwrap_or_default());
+
+    db_conn.transaction(|conn| {
+        let tag_ids = names
+            .iter()
+            .map(|name| get_or_create_tag(conn, name))
+            .collect::<Result<Vec<_>>>()?;
+
+        diesel::delete(
+            dsl::preview_tags
+                .filter(dsl::preview_url.eq(&preview.url))
+                .filter(dsl::provenance.eq(provenance.to_string()))
+                .filter(dsl::tag_id.ne_all(&tag_ids)),
+        )
+        .execute(conn)?;
+
+        for tag_id in tag_ids {
+            diesel::insert_or_ignore_into(dsl::preview_tags)
+                .values(PreviewTag {
+                    preview_url: preview.url.clone(),
+                    tag_id,
+                    provenance: provenance.to_string(),
+                })
+                .execute(conn)?;
+        }
+
+        refresh_tags_column(conn, &preview.url)
+    })
+}
+
+/// Adds tags to a stored preview.
+pub fn add_preview_tags(
+    db_conn: &mut SqliteConnection,
+    url: &str,
+    names: &[String],
+    provenance: TagProvenance,
+) -> Result<()> {
+    use crate::schema::preview_tags::dsl;
+
+    db_conn.transaction(|conn| {
+        for name in names.iter().filter_map(|name| normalize_tag(name)) {
+            let tag_id = get_or_create_tag(conn, &name)?;
+            diesel::insert_or_ignore_into(dsl::preview_tags)
+                .values(PreviewTag {
+                    preview_url: url.to_owned(),
+                    tag_id,
+                    provenance: provenance.to_string(),
+                })
+                .execute(conn)?;
+        }
+        refresh_tags_column(conn, url)
+    })
+}
+
+/// Removes a tag from a stored preview. Returns whether the preview had it.
+pub fn remove_preview_tag(db_conn: &mut SqliteConnection, url: &str, name: &str) -> Result<bool> {
+    use crate::schema::{preview_tags::dsl, tags};
+
+    let Some(name) = normalize_tag(name) else {
+        return Ok(false);
+    };
+
+    db_conn.transaction(|conn| {
+        let tag_ids = tags::table.filter(tags::name.eq(&name)).select(tags::id);
+        let deleted = diesel::delete(
+            dsl::preview_tags
+                .filter(dsl::preview_url.eq(url))
+                .filter(dsl::tag_id.eq_any(tag_ids)),
+        )
+        .execute(conn)?;
+        refresh_tags_column(conn, url)?;
+        Ok(deleted > 0)
+    })
+}
+
+/// Lists every tag with the number of previews that have it, most used first.
+pub fn list_tags(db_conn: &mut SqliteConnection) -> Result<Vec<TagCount>> {
+    Ok(diesel::sql_query(
+        "SELECT tags.name AS name, count(preview_tags.preview_url) AS count \
+         FROM tags LEFT JOIN preview_tags ON preview_tags.tag_id = tags.id \
+         GROUP BY tags.id \
+         ORDER BY count DESC, tags.name",
+    )
+    .load(db_conn)?)
+}
+
+/// Loads the previews that have the tag.
+pub fn get_previews_by_tag(db_conn: &mut SqliteConnection, name: &str) -> Result<Vec<Preview>> {
+    use crate::schema::{preview_tags, previews, tags};
+
+    let Some(name) = normalize_tag(name) else {
+        return Ok(vec![]);
+    };
+
+    Ok(previews::table
+        .inner_join(preview_tags::table.inner_join(tags::table))
+        .filter(tags::name.eq(name))
+        .select(Preview::as_select())
+        .load(db_conn)?)
+}
+
+/// Renames a tag. If a tag with the new name already exists, the tag is merged
+/// into it instead.
+pub fn rename_tag(db_conn: &mut SqliteConnection, from: &str, to: &str) -> Result<()> {
+    merge_tags(db_conn, &[from.to_owned()], to)
+}
+
+/// Merges the tags named `froms` into the tag named `into`, which is created
+/// if it doesn't exist yet. The provenance of each merged tag is kept.
+pub fn merge_tags(db_conn: &mut SqliteConnection, froms: &[String], into: &str) -> Result<()> {
+    use crate::schema::{preview_tags, tags};
+
+    let Some(into) = normalize_tag(into) else {
+        return Ok(());
+    };
+
+    db_conn.transaction(|conn| {
+        let into_id = get_or_create_tag(conn, &into)?;
+        let mut urls: Vec<String> = vec![];
+
+        for from in froms.iter().filter_map(|from| normalize_tag(from)) {
+            let Some(from_id) = tags::table
+                .filter(tags::name.eq(&from))
+                .select(tags::id)
+                .first::<i32>(conn)
+                .optional()?
+            else {
+                log::warn!["unknown tag: {from}"];
+                continue;
+            };
+            if from_id == into_id {
+                continue;
+            }
+
+            let from_preview_tags = preview_tags::table
+                .filter(preview_tags::tag_id.eq(from_id))
+                .select(PreviewTag::as_select())
+                .load(conn)?;
+            for preview_tag in from_preview_tags {
+                diesel::insert_or_ignore_into(preview_tags::table)
+                    .values(PreviewTag {
+                        tag_id: into_id,
+                        ..preview_tag.clone()
+                    })
+                    .execute(conn)?;
+                urls.push(preview_tag.preview_url);
+            }
+
+            diesel::delete(preview_tags::table.filter(preview_tags::tag_id.eq(from_id)))
+                .execute(conn)?;
+            diesel::delete(tags::table.find(from_id)).execute(conn)?;
+        }
+
+        for url in urls {
+            refresh_tags_column(conn, &url)?;
+        }
+        Ok(())
+    })
+}
+
+/// Relates the stored previews whose `previews.tags` were written before tags
+/// were normalized to those tags, with [`TagProvenance::Legacy`]. Returns the
+/// number of previews that were backfilled.
+pub fn backfill_legacy_tags(db_conn: &mut SqliteConnection) -> Result<usize> {
+    use crate::schema::{preview_tags, previews};
+
+    let legacy: Vec<(String, String)> = previews::table
+        .filter(previews::tags.is_not_null())
+        .filter(not(exists(
+            preview_tags::table.filter(preview_tags::preview_url.eq(previews::url)),
+        )))
+        .select((previews::url, previews::tags.assume_not_null()))
+        .load(db_conn)?;
+    for (url, tags) in &legacy {
+        // also clears `previews.tags` if none of them are left once normalized
+        add_preview_tags(db_conn, url, &normalize_tags(tags), TagProvenance::Legacy)?;
+    }
+    Ok(legacy.len())
+}
+
+/// Rewrites `previews.tags` of the preview from its related tags.
+pub fn refresh_tags_column(db_conn: &mut SqliteConnection, url: &str) -> Result<()> {
+    diesel::sql_query(
+        "UPDATE previews SET tags = ( \
+           SELECT group_concat(name, ', ') FROM ( \
+             SELECT tags.name FROM preview_tags JOIN tags ON tags.id = preview_tags.tag_id \
+             WHERE preview_tags.preview_url = previews.url \
+             ORDER BY tags.name \
+           ) \
+         ) \
+         WHERE url = ?",
+    )
+    .bind::<Text, _>(url)
+    .execute(db_conn)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::db;
+
+    fn stored_tags(db_conn: &mut SqliteConnection, url: &str) -> Option<String> {
+        db::get_preview(db_conn, url.to_owned())
+            .unwrap()
+            .unwrap()
+            .tags
+    }
+
+    #[test]
+    fn normalizes_tags() {
+        assert_eq!(
+            normalize_tag("  Type \t Theory. ").as_deref(),
+            Some("type theory")
+        );
+        assert_eq!(normalize_tag("ÉCOLE").as_deref(), Some("école"));
+        assert_eq!(normalize_tag(" . "), None);
+        assert_eq!(
+            normalize_tags("Rust, PL,, rust ,Type  Theory"),
+            vec!["rust", "pl", "type theory"]
+        );
+    }
+
+    #[test]
+    fn syncs_preview_tags_of_each_provenance() {
+        let mut db_conn = db::test_connection();
+        let mut preview = Preview::from_url("https://example.com/post".to_owned());
+        preview.tags = Some("Rust, PL".to_owned());
+        preview.tags_provenance = Some(TagProvenance::Rss.to_string());
+        db::insert_preview(&mut db_conn, &preview).unwrap();
+        add_preview_tags(
+            &mut db_conn,
+            &preview.url,
+            &["Effects".to_owned()],
+            TagProvenance::Manual,
+        )
+        .unwrap();
+        assert_eq!(
+            stored_tags(&mut db_conn, &preview.url).as_deref(),
+            Some("effects, pl, rust")
+        );
+
+        // only the tags of the same provenance are replaced
+        preview.tags = Some("rust".to_owned());
+        db::update_preview(&mut db_conn, &preview).unwrap();
+        assert_eq!(
+            stored_tags(&mut db_conn, &preview.url).as_deref(),
+            Some("effects, rust")
+        );
+
+        assert!(remove_preview_tag(&mut db_conn, &preview.url, "EFFECTS").unwrap());
+        assert!(!remove_preview_tag(&mut db_conn, &preview.url, "effects").unwrap());
+        assert_eq!(
+            stored_tags(&mut db_conn, &preview.url).as_deref(),
+            Some("rust")
+        );
+        assert_eq!(get_previews_by_tag(&mut db_conn, "Rust").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn merges_tags() {
+        let mut db_conn = db::test_connection();
+        for (url, tags) in [
+            ("https://example.com/a", "pl"),
+            ("https://example.com/b", "programming languages, rust"),
+        ] {
+            let mut preview = Preview::from_url(url.to_owned());
+            preview.tags = Some(tags.to_owned());
+            db::insert_preview(&mut db_conn, &preview).unwrap();
+        }
+
+        merge_tags(
+            &mut db_conn,
+            &["PL".to_owned(), "Programming Languages".to_owned()],
+            "programming-languages",
+        )
+        .unwrap();
+        rename_tag(&mut db_conn, "rust", "Rust Lang").unwrap();
+
+        let tags = list_tags(&mut db_conn).unwrap();
+        let tags: Vec<(&str, i64)> = tags
+            .iter()
+            .map(|tag| (tag.name.as_str(), tag.count))
+            .collect();
+        assert_eq!(tags, vec![("programming-languages", 2), ("rust lang", 1)]);
+        assert_eq!(
+            stored_tags(&mut db_conn, "https://example.com/b").as_deref(),
+            Some("programming-languages, rust lang")
+        );
+    }
+
+    #[test]
+    fn backfills_legacy_tags() {
+        let mut db_conn = db::test_connection();
+        diesel::sql_query(
+            "INSERT INTO previews (url, added_date, tags) VALUES \
+               ('https://example.com/a', '2026-10-01', 'Type  Theory, TYPE theory., Émile'), \
+               ('https://example.com/b', '2026-10-01', ' , '), \
+               ('https://example.com/c', '2026-10-01', NULL)",
+        )
+        .execute(&mut db_conn)
+        .unwrap();
+
+        assert_eq!(backfill_legacy_tags(&mut db_conn).unwrap(), 2);
+        assert_eq!(
+            stored_tags(&mut db_conn, "https://example.com/a").as_deref(),
+            Some("type theory, émile")
+        );
+        assert_eq!(stored_tags(&mut db_conn, "https://example.com/b"), None);
+        let tags = list_tags(&mut db_conn).unwrap();
+        assert_eq!(tags.len(), 2);
+
+        // once backfilled, nothing is left to backfill
+        assert_eq!(backfill_legacy_tags(&mut db_conn).unwrap(), 0);
+    }
+}
@@ -1,4 +1,36 @@
+//! Posts on [X](https://x.com), formerly Twitter, from its oEmbed API.
+//!
+//! Posts are recognized on X's and Twitter's hosts, and on the mirrors that
+//! embed them better in chats, like fxtwitter. The oEmbed API responds with a
+//! blockquote of the post, which [`parse_post_html`] extracts the text, author
+//! handle, posted date and outbound links from.
+use crate::{config, utility::http::Fetcher};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use url::Url;
+
+/// The default URL of the oEmbed API, which the `X_OEMBED_URL` environment
+/// variable overrides.
+pub const DEFAULT_OEMBED_URL: &str = "https://publish.twitter.com/oembed";
+
+/// The hosts that posts are recognized on.
+const HOSTS: [&str; 8] = [
+    "x.com",
+    "twitter.com",
+    "mobile.twitter.com",
+    "mobile.x.com",
+    "fxtwitter.com",
+    "vxtwitter.com",
+    "fixupx.com",
+    "fixvx.com",
+];
+
+/// The hosts of the links that posts embed which are part of the post itself,
+/// like hashtags, mentions and attached media, rather than outbound links.
+const INTERNAL_HOSTS: [&str; 4] = ["x.com", "twitter.com", "pic.twitter.com", "pic.x.com"];
+
+/// The host of X's link shortener, which every outbound link goes through.
+const SHORTENER_HOST: &str = "t.co";
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Post {
@@ -8,9 +40,284 @@ pub struct Post {
     pub html: String,
 }
 
-pub async fn fetch_post(post_url: &str) -> Result<Post> {
-    let post_url = urlencoding::encode(post_url);
-    let result = reqwest::get(format!("https://publish.twitter.com/oembed?url={post_url}")).await?;
-    let post = result.json::<Post>().await?;
-    Ok(post)
+impl Post {
+    /// The handle of the author, without the `@`, from their profile URL.
+    pub fn author_handle(&self) -> Option<String> {
+        let url = Url::parse(&self.author_url).ok()?;
+        let handle = url.path_segments()?.find(|s| !s.is_empty())?;
+        Some(handle.to_owned())
+    }
+}
+
+/// A post, as identified by its URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostRef {
+    pub handle: String,
+    pub id: String,
+}
+
+impl PostRef {
+    /// Parses a URL like `https://twitter.com/{handle}/status/{id}` on any of
+    /// the recognized hosts, also with `www.`.
+    pub fn parse(url: &str) -> Option<PostRef> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?;
+        if !HOSTS.contains(&host.strip_prefix("www.").unwrap_or(host)) {
+            return None;
+        }
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        match segments[..] {
+            [handle, "status" | "statuses", id, ..]
+                if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                Some(PostRef {
+                    handle: handle.to_owned(),
+                    id: id.to_owned(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The canonical URL of the post, on x.com.
+    pub fn url(&self) -> String {
+        format!("https://x.com/{}/status/{}", self.handle, self.id)
+    }
+}
+
+/// The URL of the oEmbed API.
+pub fn oembed_url() -> &'static str {
+    config::X_OEMBED_URL
+        .as_deref()
+        .unwrap_or(DEFAULT_OEMBED_URL)
+}
+
+/// Fetches the post from the oEmbed API at `oembed_url`, see [`oembed_url`].
+pub async fn fetch_post(fetcher: &Fetcher, oembed_url: &str, post_url: &str) -> Result<Post> {
+    // the oEmbed API only knows the URLs of posts on twitter.com and x.com
+    let post_url = PostRef::parse(post_url)
+        .map(|post_ref| post_ref.url())
+        .unwrap_or(post_url.to_owned());
+    let post_url = urlencoding::encode(&post_url);
+    let response = fetcher
+        .get(&format!("{oembed_url}?url={post_url}&omit_script=true"))
+        .await?;
+    response.json::<Post>()
+}
+
+/// What the blockquote of a post says.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PostContent {
+    /// The text of the post, with its line breaks.
+    pub text: String,
+    /// The handle of the author, without the `@`.
+    pub handle: Option<String>,
+    /// The day the post was posted, at midnight UTC, since the blockquote
+    /// doesn't say the time.
+    pub posted: Option<DateTime<Utc>>,
+    /// The links in the text of the post that lead off of X, as shortened by
+    /// it, e.g. `https://t.co/abc123`.
+    pub links: Vec<String>,
+}
+
+/// Parses the blockquote that the oEmbed API responds with, which looks like
+///
+/// ```html
+/// <blockquote class="twitter-tweet"><p lang="en" dir="ltr">just setting up my
+/// twttr <a href="https://t.co/abc">example.com/page</a></p>&mdash; jack
+/// (@jack) <a href="https://twitter.com/jack/status/20">March 21, 2006</a>
+/// </blockquote>
+/// ```
+pub fn parse_post_html(html: &str) -> PostContent {
+    let html = scraper::Html::parse_fragment(&html.replace("<br>", "\n"));
+    let paragraph_selector = scraper::Selector::parse("blockquote p").unwrap();
+    let link_selector = scraper::Selector::parse("a[href]").unwrap();
+    let blockquote_link_selector = scraper::Selector::parse("blockquote > a[href]").unwrap();
+    let blockquote_selector = scraper::Selector::parse("blockquote").unwrap();
+
+    let mut content = PostContent::default();
+    let mut paragraphs = vec![];
+    for paragraph in html.select(&paragraph_selector) {
+        paragraphs.push(paragraph.text().collect::<String>().trim().to_owned());
+        for link in paragraph.select(&link_selector) {
+            let Some(href) = link.value().attr("href") else {
+                continue;
+            };
+            // attached media are shortened too, but show where they're hosted
+            let text = link.text().collect::<String>();
+            let is_media = INTERNAL_HOSTS
+                .iter()
+                .any(|host| text.trim().starts_with(&format!("{host}/")));
+            if is_outbound_link(href) && !is_media && !content.links.iter().any(|l| l == href) {
+                content.links.push(href.to_owned());
+            }
+        }
+    }
+    content.text = paragraphs.join("\n\n");
+
+    // the byline after the text: `— Name (@handle) <a>date</a>`
+    if let Some(blockquote) = html.select(&blockquote_selector).next() {
+        let byline: String = blockquote
+            .children()
+            .filter_map(|node| node.value().as_text())
+            .map(|text| text.to_string())
+            .collect();
+        content.handle = byline
+            .rsplit_once("(@")
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(handle, _)| handle.trim().to_owned());
+    }
+    content.posted = html
+        .select(&blockquote_link_selector)
+        .next_back()
+        .and_then(|link| super::date::parse_date(link.text().collect::<String>().trim()));
+
+    content
+}
+
+fn is_outbound_link(href: &str) -> bool {
+    let Ok(url) = Url::parse(href) else {
+        return false;
+    };
+    match url.host_str() {
+        Some(host) => {
+            let host = host.strip_prefix("www.").unwrap_or(host);
+            !(INTERNAL_HOSTS.contains(&host) || HOSTS.contains(&host))
+        }
+        None => false,
+    }
+}
+
+/// Follows a link through X's link shortener to where it leads. Other links,
+/// and links that can't be followed, are returned as they are.
+pub async fn resolve_link(fetcher: &Fetcher, link: &str) -> String {
+    let is_shortened = Url::parse(link)
+        .ok()
+        .is_some_and(|url| url.host_str() == Some(SHORTENER_HOST));
+    if !is_shortened {
+        return link.to_owned();
+    }
+    match fetcher.get(link).await {
+        Ok(response) => response.url,
+        Err(e) => {
+            log::warn!["failed to follow {link}: {e}"];
+            link.to_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utility::mock_server::mock_server;
+
+    const HTML: &str = r#"<blockquote class="twitter-tweet"><p lang="en" dir="ltr">Effects, at last:<br><a href="https://t.co/abc">example.com/effects</a> with <a href="https://twitter.com/grace?ref_src=twsrc%5Etfw">@grace</a> <a href="https://twitter.com/hashtag/rust?src=hash">#rust</a> <a href="https://t.co/pic">pic.twitter.com/xyz</a> <a href="https://t.co/abc">again</a></p>&mdash; Ada Lovelace (@ada) <a href="https://twitter.com/ada/status/1846?ref_src=twsrc%5Etfw">October 17, 2026</a></blockquote>"#;
+
+    const OEMBED: &str = r#"{
+        "url": "https://twitter.com/ada/status/1846",
+        "author_name": "Ada Lovelace",
+        "author_url": "https://twitter.com/ada",
+        "html": "<blockquote class=\"twitter-tweet\"><p>Hello</p>&mdash; Ada Lovelace (@ada) <a href=\"https://twitter.com/ada/status/1846\">October 17, 2026</a></blockquote>"
+    }"#;
+
+    #[test]
+    fn parses_post_urls() {
+        let ada = PostRef {
+            handle: "ada".to_owned(),
+            id: "1846".to_owned(),
+        };
+        assert_eq!(ada.url(), "https://x.com/ada/status/1846");
+        let post_ref = Some(ada);
+        for host in HOSTS {
+            let url = format!("https://{host}/ada/status/1846");
+            assert_eq!(PostRef::parse(&url), post_ref, "{url}");
+        }
+        for url in [
+            "https://www.twitter.com/ada/status/1846",
+            "https://x.com/ada/statuses/1846",
+            "https://x.com/ada/status/1846/photo/1",
+            "https://x.com/ada/status/1846?s=20#reply",
+        ] {
+            assert_eq!(PostRef::parse(url), post_ref, "{url}");
+        }
+
+        for url in [
+            "https://x.com/ada",
+            "https://x.com/ada/status/",
+            "https://x.com/ada/status/latest",
+            "https://example.com/ada/status/1846",
+            "https://nitter.net/ada/status/1846",
+        ] {
+            assert_eq!(PostRef::parse(url), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn parses_post_html() {
+        let content = parse_post_html(HTML);
+        assert_eq!(
+            content.text,
+            "Effects, at last:\nexample.com/effects with @grace #rust pic.twitter.com/xyz again"
+        );
+        assert_eq!(content.handle.as_deref(), Some("ada"));
+        assert_eq!(
+            content.posted,
+            Some(
+                DateTime::parse_from_rfc3339("2026-10-17T00:00:00Z")
+                    .unwrap()
+                    .to_utc()
+            )
+        );
+        // without mentions, hashtags, attached media or repeats
+        assert_eq!(content.links, ["https://t.co/abc"]);
+    }
+
+    #[test]
+    fn parses_post_html_without_a_byline() {
+        let content = parse_post_html(r#"<blockquote><p>Just text</p></blockquote>"#);
+        assert_eq!(content.text, "Just text");
+        assert_eq!(content.handle, None);
+        assert_eq!(content.posted, None);
+        assert!(content.links.is_empty());
+    }
+
+    #[test]
+    fn tells_outbound_links() {
+        assert!(is_outbound_link("https://t.co/abc"));
+        assert!(is_outbound_link("https://example.com/page"));
+        for href in [
+            "https://twitter.com/hashtag/rust",
+            "https://www.x.com/grace",
+            "https://pic.x.com/xyz",
+            "https://fxtwitter.com/ada/status/1846",
+            "/relative",
+            "mailto:ada@example.com",
+        ] {
+            assert!(!is_outbound_link(href), "{href}");
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_posts() {
+        let oembed_url = format!("{}/oembed", mock_server(vec![("/oembed", OEMBED)]).await);
+        let fetcher = Fetcher::new().unwrap();
+
+        let post = fetch_post(
+            &fetcher,
+            &oembed_url,
+            "https://fxtwitter.com/ada/status/1846",
+        )
+        .await
+        .unwrap();
+        assert_eq!(post.author_name, "Ada Lovelace");
+        assert_eq!(post.author_handle().as_deref(), Some("ada"));
+        assert_eq!(parse_post_html(&post.html).text, "Hello");
+
+        let oembed_url = format!("{}/oembed", mock_server(vec![]).await);
+        assert!(
+            fetch_post(&fetcher, &oembed_url, "https://x.com/ada/status/1846")
+                .await
+                .is_err()
+        );
+    }
 }